@@ -5,6 +5,7 @@ import { Program, AnchorProvider, BN } from '@coral-xyz/anchor';
 import { PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from '@solana/web3.js';
 import { 
   TOKEN_PROGRAM_ID, 
+  TOKEN_2022_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
   getAssociatedTokenAddress,
   getAccount,
@@ -16,9 +17,21 @@ import idl from '../idl/vault_program.json';
 // Program ID from the IDL
 const PROGRAM_ID = new PublicKey('D3ioGqnnBE4CkW7TN3Cb7Va2BG1sb4VE5vk5KKYoogwx');
 
+// Share transfer hook attached to IOU mints that are not non-transferable
+const SHARE_TRANSFER_HOOK_PROGRAM_ID = new PublicKey('97VVh6nbpScmjWxDKLriqayDTu2CmAztchYj6WBusXub');
+
 // Rate precision constant
 const RATE_PRECISION = 1_000_000_000;
 
+// Default metadata written to the IOU mint on initialize
+const DEFAULT_IOU_NAME = 'Vault IOU';
+const DEFAULT_IOU_SYMBOL = 'vIOU';
+const DEFAULT_IOU_URI = '';
+
+// Anchor enum encodings of the program's EpochMode and ShareTransferMode
+export type EpochMode = { unixSeconds: object } | { slots: object } | { solanaEpoch: object };
+export type ShareTransferMode = { transferable: object } | { nonTransferable: object } | { transferHook: object };
+
 export interface VaultData {
   authority: PublicKey;
   depositTokenMint: PublicKey;
@@ -28,8 +41,24 @@ export interface VaultData {
   totalShares: BN;
   rate: BN;
   currentEpoch: BN;
+  epochMode: EpochMode;
   epochDuration: BN;
   lastEpochTimestamp: BN;
+  lastEpochSlot: BN;
+  lastSolanaEpoch: BN;
+  interestBearingIou: boolean;
+  shareTransferMode: ShareTransferMode;
+  navOracle: PublicKey;
+  navMaxStaleness: BN;
+  navMaxConfidenceBps: number;
+  navSyncedSlot: BN;
+  deployedAssets: BN;
+  pendingWithdrawals: BN;
+  idleBufferBps: number;
+  lossReporter: PublicKey;
+  withdrawalLossIndex: BN;
+  surplusRecipient: PublicKey;
+  rewardCampaign: PublicKey;
   bump: number;
   iouMintBump: number;
 }
@@ -37,10 +66,12 @@ export interface VaultData {
 export interface WithdrawalRequestData {
   user: PublicKey;
   vault: PublicKey;
+  receiver: PublicKey;
   sharesAmount: BN;
   tokensToReceive: BN;
   requestEpoch: BN;
   claimableEpoch: BN;
+  lossIndex: BN;
   claimed: boolean;
   bump: number;
 }
@@ -94,6 +125,26 @@ export function useVaultProgram() {
     );
   };
 
+  // Derive user position PDA
+  const getUserPositionPDA = (vault: PublicKey, user: PublicKey) => {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('position'), vault.toBuffer(), user.toBuffer()],
+      PROGRAM_ID
+    );
+  };
+
+  // Reward accounts a share-changing instruction must pass while the vault has an active campaign
+  const getRewardAccounts = (vault: VaultData, user: PublicKey) => {
+    if (vault.rewardCampaign.equals(PublicKey.default)) {
+      return { rewardCampaign: null, rewardCheckpoint: null };
+    }
+    const [rewardCheckpoint] = PublicKey.findProgramAddressSync(
+      [Buffer.from('reward_checkpoint'), vault.rewardCampaign.toBuffer(), user.toBuffer()],
+      PROGRAM_ID
+    );
+    return { rewardCampaign: vault.rewardCampaign, rewardCheckpoint };
+  };
+
   // Fetch vault data
   const fetchVault = useCallback(async (vaultAddress: PublicKey): Promise<VaultData | null> => {
     if (!program) return null;
@@ -125,7 +176,13 @@ export function useVaultProgram() {
   // Initialize vault
   const initialize = async (
     depositTokenMint: PublicKey,
-    epochDuration: number
+    epochDuration: number,
+    epochMode: EpochMode = { unixSeconds: {} },
+    iouName: string = DEFAULT_IOU_NAME,
+    iouSymbol: string = DEFAULT_IOU_SYMBOL,
+    iouUri: string = DEFAULT_IOU_URI,
+    interestBearingIou: boolean = false,
+    shareTransferMode: ShareTransferMode = { transferable: {} }
   ): Promise<string | null> => {
     if (!program || !wallet.publicKey) {
       toast.error('Please connect your wallet');
@@ -143,14 +200,22 @@ export function useVaultProgram() {
 
       // @ts-expect-error - Anchor types are too deep for TypeScript
       const tx = await program.methods
-        .initialize(new BN(epochDuration))
+        .initialize(
+          new BN(epochDuration),
+          epochMode,
+          { name: iouName, symbol: iouSymbol, uri: iouUri },
+          interestBearingIou,
+          shareTransferMode
+        )
         .accounts({
           authority: wallet.publicKey,
           depositTokenMint,
           vault: vaultPDA,
           iouTokenMint: iouMintPDA,
           vaultTokenAccount,
+          transferHookProgram: 'nonTransferable' in shareTransferMode ? null : SHARE_TRANSFER_HOOK_PROGRAM_ID,
           tokenProgram: TOKEN_PROGRAM_ID,
+          iouTokenProgram: TOKEN_2022_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
           rent: SYSVAR_RENT_PUBKEY,
@@ -170,7 +235,9 @@ export function useVaultProgram() {
   const deposit = async (
     vaultAddress: PublicKey,
     amount: number,
-    decimals: number
+    decimals: number,
+    minSharesOut: number = 0,
+    deadline: number | null = null
   ): Promise<string | null> => {
     if (!program || !wallet.publicKey) {
       toast.error('Please connect your wallet');
@@ -190,8 +257,11 @@ export function useVaultProgram() {
       );
       const userIouAccount = await getAssociatedTokenAddress(
         vault.iouTokenMint,
-        wallet.publicKey
+        wallet.publicKey,
+        false,
+        TOKEN_2022_PROGRAM_ID
       );
+      const [userPosition] = getUserPositionPDA(vaultAddress, wallet.publicKey);
 
       // Check if user has the token account with sufficient balance
       try {
@@ -212,16 +282,19 @@ export function useVaultProgram() {
       const amountBN = new BN(amount * Math.pow(10, decimals));
 
       const tx = await program.methods
-        .deposit(amountBN)
+        .deposit(amountBN, new BN(minSharesOut), deadline === null ? null : new BN(deadline))
         .accounts({
           user: wallet.publicKey,
           vault: vaultAddress,
           userTokenAccount,
           userIouAccount,
+          userPosition,
+          ...getRewardAccounts(vault, wallet.publicKey),
           vaultTokenAccount: vault.vaultTokenAccount,
           depositTokenMint: vault.depositTokenMint,
           iouTokenMint: vault.iouTokenMint,
           tokenProgram: TOKEN_PROGRAM_ID,
+          iouTokenProgram: TOKEN_2022_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
@@ -240,7 +313,10 @@ export function useVaultProgram() {
   const requestWithdrawal = async (
     vaultAddress: PublicKey,
     sharesAmount: number,
-    decimals: number
+    decimals: number,
+    minTokensOut: number = 0,
+    deadline: number | null = null,
+    receiver: PublicKey | null = null
   ): Promise<string | null> => {
     if (!program || !wallet.publicKey) {
       toast.error('Please connect your wallet');
@@ -257,20 +333,30 @@ export function useVaultProgram() {
       const [withdrawalRequestPDA] = getWithdrawalRequestPDA(vaultAddress, wallet.publicKey);
       const userIouAccount = await getAssociatedTokenAddress(
         vault.iouTokenMint,
-        wallet.publicKey
+        wallet.publicKey,
+        false,
+        TOKEN_2022_PROGRAM_ID
       );
+      const [userPosition] = getUserPositionPDA(vaultAddress, wallet.publicKey);
 
       const sharesAmountBN = new BN(sharesAmount * Math.pow(10, decimals));
 
       const tx = await program.methods
-        .requestWithdrawal(sharesAmountBN)
+        .requestWithdrawal(
+          sharesAmountBN,
+          new BN(minTokensOut),
+          deadline === null ? null : new BN(deadline),
+          receiver
+        )
         .accounts({
           user: wallet.publicKey,
           vault: vaultAddress,
           withdrawalRequest: withdrawalRequestPDA,
           userIouAccount,
+          userPosition,
+          ...getRewardAccounts(vault, wallet.publicKey),
           iouTokenMint: vault.iouTokenMint,
-          tokenProgram: TOKEN_PROGRAM_ID,
+          iouTokenProgram: TOKEN_2022_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
         .rpc();
@@ -299,10 +385,19 @@ export function useVaultProgram() {
       }
 
       const [withdrawalRequestPDA] = getWithdrawalRequestPDA(vaultAddress, wallet.publicKey);
-      const userTokenAccount = await getAssociatedTokenAddress(
+      const request = await fetchWithdrawalRequest(vaultAddress, wallet.publicKey);
+      if (!request) {
+        toast.error('No withdrawal request found');
+        return null;
+      }
+
+      // Claims pay out to the receiver named on the request
+      const receiverTokenAccount = await getAssociatedTokenAddress(
         vault.depositTokenMint,
-        wallet.publicKey
+        request.receiver,
+        true
       );
+      const [userPosition] = getUserPositionPDA(vaultAddress, wallet.publicKey);
 
       const tx = await program.methods
         .claimWithdrawal()
@@ -310,9 +405,13 @@ export function useVaultProgram() {
           user: wallet.publicKey,
           vault: vaultAddress,
           withdrawalRequest: withdrawalRequestPDA,
-          userTokenAccount,
+          userPosition,
+          receiverTokenAccount,
           vaultTokenAccount: vault.vaultTokenAccount,
           depositTokenMint: vault.depositTokenMint,
+          strategy: null,
+          strategyProgram: null,
+          strategyTokenAccount: null,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
         .rpc();
@@ -359,7 +458,9 @@ export function useVaultProgram() {
           authorityTokenAccount,
           vaultTokenAccount: vault.vaultTokenAccount,
           depositTokenMint: vault.depositTokenMint,
+          iouTokenMint: vault.iouTokenMint,
           tokenProgram: TOKEN_PROGRAM_ID,
+          iouTokenProgram: TOKEN_2022_PROGRAM_ID,
         })
         .rpc();
 
@@ -433,6 +534,7 @@ export function useVaultProgram() {
     getVaultPDA,
     getIouMintPDA,
     getWithdrawalRequestPDA,
+    getUserPositionPDA,
     fetchVault,
     fetchWithdrawalRequest,
     initialize,
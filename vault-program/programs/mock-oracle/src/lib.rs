@@ -0,0 +1,122 @@
+use anchor_lang::prelude::*;
+
+declare_id!("52svo94GnLopgamUbniutEgLWHk6c2bApubkGngNKtZF");
+
+/// Mock oracle for tests: publishes price accounts with the same layout and discriminator
+/// as the Pyth receiver's `PriceUpdateV2`, with prices set directly by their write authority
+#[program]
+pub mod mock_oracle {
+    use super::*;
+
+    /// Create a fully verified price update account owned by the signer
+    pub fn initialize_price_feed(
+        ctx: Context<InitializePriceFeed>,
+        feed_id: [u8; 32],
+        price: i64,
+        conf: u64,
+        exponent: i32,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let price_update = &mut ctx.accounts.price_update;
+
+        price_update.write_authority = ctx.accounts.authority.key();
+        price_update.verification_level = VerificationLevel::Full;
+        price_update.price_message = PriceFeedMessage {
+            feed_id,
+            price,
+            conf,
+            exponent,
+            publish_time: clock.unix_timestamp,
+            prev_publish_time: clock.unix_timestamp,
+            ema_price: price,
+            ema_conf: conf,
+        };
+        price_update.posted_slot = clock.slot;
+
+        Ok(())
+    }
+
+    /// Publish a new price, `publish_time` defaults to the current time (set it in the past to simulate a stale feed)
+    pub fn set_price(
+        ctx: Context<SetPrice>,
+        price: i64,
+        conf: u64,
+        exponent: i32,
+        publish_time: Option<i64>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let price_update = &mut ctx.accounts.price_update;
+        let message = &mut price_update.price_message;
+
+        message.prev_publish_time = message.publish_time;
+        message.price = price;
+        message.conf = conf;
+        message.exponent = exponent;
+        message.publish_time = publish_time.unwrap_or(clock.unix_timestamp);
+        message.ema_price = price;
+        message.ema_conf = conf;
+        price_update.posted_slot = clock.slot;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializePriceFeed<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PriceUpdateV2::INIT_SPACE
+    )]
+    pub price_update: Account<'info, PriceUpdateV2>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPrice<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = price_update.write_authority == authority.key() @ MockOracleError::Unauthorized
+    )]
+    pub price_update: Account<'info, PriceUpdateV2>,
+}
+
+/// Same layout as the Pyth receiver's `PriceUpdateV2` account
+#[account]
+#[derive(InitSpace)]
+pub struct PriceUpdateV2 {
+    pub write_authority: Pubkey,
+    pub verification_level: VerificationLevel,
+    pub price_message: PriceFeedMessage,
+    pub posted_slot: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum VerificationLevel {
+    Partial { num_signatures: u8 },
+    Full,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub struct PriceFeedMessage {
+    pub feed_id: [u8; 32],
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    pub prev_publish_time: i64,
+    pub ema_price: i64,
+    pub ema_conf: u64,
+}
+
+#[error_code]
+pub enum MockOracleError {
+    #[msg("Only the write authority can publish prices")]
+    Unauthorized,
+}
@@ -0,0 +1,213 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{self, Burn, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+declare_id!("CmFqMUtKrhq3F1F3TLHfum4iB6W6VDNUfCzQbDYcoUt5");
+
+/// Mock strategy for tests: keeps allocated tokens in a reserve account owned by a position PDA per vault
+/// Yield is simulated by minting tokens straight into the reserve, losses by `simulate_loss`
+#[program]
+pub mod mock_strategy {
+    use super::*;
+
+    /// Create the position and reserve account a vault allocates into
+    /// The payer becomes the position authority, the only wallet allowed to call `simulate_loss`, and `caller`
+    /// the only signer the adapter instructions accept: the vault's strategy registry entry
+    pub fn initialize_position(ctx: Context<InitializePosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+
+        position.authority = ctx.accounts.payer.key();
+        position.caller = ctx.accounts.caller.key();
+        position.vault = ctx.accounts.vault.key();
+        position.reserve = ctx.accounts.reserve.key();
+        position.bump = ctx.bumps.position;
+
+        Ok(())
+    }
+
+    /// Adapter interface: the vault already moved `amount` into the reserve, which is the whole position
+    pub fn strategy_deposit(_ctx: Context<StrategyAdapter>, amount: u64) -> Result<()> {
+        msg!("Allocation of {} received", amount);
+
+        Ok(())
+    }
+
+    /// Adapter interface: send `amount` back to the vault token account
+    pub fn strategy_withdraw(ctx: Context<StrategyAdapter>, amount: u64) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let seeds = &[
+            b"position".as_ref(),
+            vault_key.as_ref(),
+            &[ctx.accounts.position.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.strategy_token_account.to_account_info(),
+                mint: ctx.accounts.deposit_token_mint.to_account_info(),
+                to: ctx.accounts.vault_token_account.to_account_info(),
+                authority: ctx.accounts.position.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token_interface::transfer_checked(transfer_ctx, amount, ctx.accounts.deposit_token_mint.decimals)
+    }
+
+    /// Burn `amount` from the reserve to simulate a strategy loss (position authority only)
+    pub fn simulate_loss(ctx: Context<SimulateLoss>, amount: u64) -> Result<()> {
+        let vault_key = ctx.accounts.position.vault;
+        let seeds = &[
+            b"position".as_ref(),
+            vault_key.as_ref(),
+            &[ctx.accounts.position.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let burn_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.deposit_token_mint.to_account_info(),
+                from: ctx.accounts.reserve.to_account_info(),
+                authority: ctx.accounts.position.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token_interface::burn(burn_ctx, amount)
+    }
+
+    /// Adapter interface: the position is worth whatever the reserve holds
+    pub fn strategy_report(ctx: Context<StrategyReport>) -> Result<u64> {
+        Ok(ctx.accounts.strategy_token_account.amount)
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializePosition<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: vault the position is opened for, only its address is used
+    pub vault: UncheckedAccount<'info>,
+
+    /// CHECK: the vault's strategy registry entry, only its address is recorded
+    pub caller: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = deposit_token_mint,
+        associated_token::authority = position,
+        associated_token::token_program = token_program
+    )]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+
+    pub deposit_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SimulateLoss<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(has_one = authority)]
+    pub position: Account<'info, Position>,
+
+    #[account(
+        mut,
+        address = position.reserve
+    )]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub deposit_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Fixed adapter accounts followed by the position as strategy-specific account
+#[derive(Accounts)]
+pub struct StrategyAdapter<'info> {
+    pub caller: Signer<'info>,
+
+    /// CHECK: vault the position belongs to, checked through the position seeds
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        token::mint = deposit_token_mint,
+        token::authority = vault
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = position.reserve
+    )]
+    pub strategy_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub deposit_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    #[account(
+        seeds = [b"position", vault.key().as_ref()],
+        bump = position.bump,
+        has_one = caller
+    )]
+    pub position: Account<'info, Position>,
+}
+
+/// Read-only adapter accounts of `strategy_report`, which is called unsigned
+#[derive(Accounts)]
+pub struct StrategyReport<'info> {
+    /// CHECK: the vault's strategy registry entry, not a signer for reports
+    pub caller: UncheckedAccount<'info>,
+
+    /// CHECK: vault the position belongs to, checked through the position seeds
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        token::mint = deposit_token_mint,
+        token::authority = vault
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = position.reserve)]
+    pub strategy_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub deposit_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    #[account(
+        seeds = [b"position", vault.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, Position>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub authority: Pubkey,
+    pub caller: Pubkey,
+    pub vault: Pubkey,
+    pub reserve: Pubkey,
+    pub bump: u8,
+}
@@ -0,0 +1,15 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum ShareTransferHookError {
+    #[msg("The mint is not the IOU mint of this vault")]
+    MintMismatch,
+    #[msg("The vault does not use the share transfer hook")]
+    TransferHookNotEnabled,
+    #[msg("The hook can only be called during a token transfer")]
+    NotTransferring,
+    #[msg("Sending wallet is not on the vault allowlist")]
+    SenderNotAllowed,
+    #[msg("Receiving wallet is not on the vault allowlist")]
+    RecipientNotAllowed,
+}
@@ -0,0 +1,98 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+use spl_tlv_account_resolution::{
+    account::ExtraAccountMeta, pubkey_data::PubkeyData, seeds::Seed, state::ExtraAccountMetaList,
+};
+use vault_program::state::{ShareTransferMode, Vault};
+
+use crate::errors::ShareTransferHookError;
+
+/// Extra accounts appended by Token-2022 after the 5 accounts of the execute instruction
+/// (source, mint, destination, owner, extra account meta list):
+/// 5. vault program, 6. vault, 7. source wallet allowlist entry, 8. destination wallet allowlist entry,
+/// 9. vault's reward campaign, 10. source wallet reward checkpoint, 11. destination wallet reward checkpoint
+pub fn extra_account_metas(vault: &Pubkey) -> Result<Vec<ExtraAccountMeta>> {
+    // Token account owner, stored right after the mint
+    let owner_of = |token_account_index: u8| Seed::AccountData {
+        account_index: token_account_index,
+        data_index: 32,
+        length: 32,
+    };
+    let allowlist_entry_of = |token_account_index: u8| {
+        ExtraAccountMeta::new_external_pda_with_seeds(
+            5,
+            &[
+                Seed::Literal { bytes: b"allowlist".to_vec() },
+                Seed::AccountKey { index: 6 },
+                owner_of(token_account_index),
+            ],
+            false,
+            false,
+        )
+    };
+    let reward_checkpoint_of = |token_account_index: u8| {
+        ExtraAccountMeta::new_external_pda_with_seeds(
+            5,
+            &[
+                Seed::Literal { bytes: b"reward_checkpoint".to_vec() },
+                Seed::AccountKey { index: 9 },
+                owner_of(token_account_index),
+            ],
+            false,
+            true,
+        )
+    };
+
+    Ok(vec![
+        ExtraAccountMeta::new_with_pubkey(&vault_program::ID, false, false)?,
+        ExtraAccountMeta::new_with_pubkey(vault, false, false)?,
+        allowlist_entry_of(0)?,
+        allowlist_entry_of(2)?,
+        // Read from the vault account, so starting a new campaign needs no update of the list
+        ExtraAccountMeta::new_with_pubkey_data(
+            &PubkeyData::AccountData {
+                account_index: 6,
+                data_index: Vault::REWARD_CAMPAIGN_OFFSET as u8,
+            },
+            false,
+            false,
+        )?,
+        reward_checkpoint_of(0)?,
+        reward_checkpoint_of(2)?,
+    ])
+}
+
+#[derive(Accounts)]
+pub struct InitializeExtraAccountMetaList<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Extra account metas read by Token-2022 on each transfer (PDA per mint, fixed by the interface)
+    /// CHECK: written by the handler
+    #[account(
+        init,
+        payer = payer,
+        space = ExtraAccountMetaList::size_of(extra_account_metas(&vault.key())?.len())?,
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    /// IOU mint of the vault
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        constraint = vault.iou_token_mint == mint.key() @ ShareTransferHookError::MintMismatch,
+        constraint = vault.share_transfer_mode != ShareTransferMode::NonTransferable
+            @ ShareTransferHookError::TransferHookNotEnabled
+    )]
+    pub vault: Account<'info, Vault>,
+
+    pub system_program: Program<'info, System>,
+}
+
+
+
+
+
+
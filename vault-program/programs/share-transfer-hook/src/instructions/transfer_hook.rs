@@ -0,0 +1,72 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use vault_program::program::VaultProgram;
+use vault_program::state::Vault;
+
+use crate::errors::ShareTransferHookError;
+
+/// Accounts of the transfer hook execute instruction, in the order Token-2022 passes them
+#[derive(Accounts)]
+pub struct TransferHook<'info> {
+    #[account(token::mint = mint)]
+    pub source_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(token::mint = mint)]
+    pub destination_token: InterfaceAccount<'info, TokenAccount>,
+
+    /// Source token account owner or delegate
+    /// CHECK: already authorized by Token-2022
+    pub owner: UncheckedAccount<'info>,
+
+    /// CHECK: extra account meta list of the mint, only checked by address
+    #[account(
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    pub vault_program: Program<'info, VaultProgram>,
+
+    #[account(
+        constraint = vault.iou_token_mint == mint.key() @ ShareTransferHookError::MintMismatch
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Allowlist entry of the sending wallet, only read with `ShareTransferMode::TransferHook`
+    /// (wallets never allowlisted have no entry)
+    /// CHECK: PDA checked by seeds, loaded by the handler
+    #[account(
+        seeds = [b"allowlist", vault.key().as_ref(), source_token.owner.as_ref()],
+        seeds::program = vault_program.key(),
+        bump
+    )]
+    pub source_allowlist: UncheckedAccount<'info>,
+
+    /// Allowlist entry of the receiving wallet
+    /// CHECK: PDA checked by seeds, loaded by the handler
+    #[account(
+        seeds = [b"allowlist", vault.key().as_ref(), destination_token.owner.as_ref()],
+        seeds::program = vault_program.key(),
+        bump
+    )]
+    pub destination_allowlist: UncheckedAccount<'info>,
+
+    /// CHECK: vault's reward campaign, checked by the vault program
+    pub reward_campaign: UncheckedAccount<'info>,
+
+    /// CHECK: sending wallet's reward checkpoint, checked by the vault program
+    #[account(mut)]
+    pub source_checkpoint: UncheckedAccount<'info>,
+
+    /// CHECK: receiving wallet's reward checkpoint, checked by the vault program
+    #[account(mut)]
+    pub destination_checkpoint: UncheckedAccount<'info>,
+}
+
+
+
+
+
+
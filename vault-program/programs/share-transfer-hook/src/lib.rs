@@ -0,0 +1,85 @@
+use anchor_lang::prelude::*;
+use spl_discriminator::SplDiscriminate;
+use spl_tlv_account_resolution::state::ExtraAccountMetaList;
+use spl_transfer_hook_interface::instruction::ExecuteInstruction;
+use vault_program::cpi::accounts::CheckpointShareTransfer;
+use vault_program::state::{AllowlistEntry, ShareTransferMode};
+use vault_program::utils::is_transferring;
+
+pub mod errors;
+pub mod instructions;
+
+use errors::*;
+use instructions::*;
+
+declare_id!("97VVh6nbpScmjWxDKLriqayDTu2CmAztchYj6WBusXub");
+
+#[program]
+pub mod share_transfer_hook {
+    use super::*;
+
+    /// Write the extra accounts Token-2022 must pass to the hook for a vault's IOU mint
+    /// Needs to run once per vault with transferable or transfer hook shares, before any share transfer
+    pub fn initialize_extra_account_meta_list(ctx: Context<InitializeExtraAccountMetaList>) -> Result<()> {
+        let extra_account_metas = extra_account_metas(&ctx.accounts.vault.key())?;
+        ExtraAccountMetaList::init::<ExecuteInstruction>(
+            &mut ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?,
+            &extra_account_metas,
+        )?;
+
+        msg!("Share transfer hook enabled for IOU mint: {}", ctx.accounts.mint.key());
+
+        Ok(())
+    }
+
+    /// Called by Token-2022 on every IOU share transfer
+    /// With `ShareTransferMode::TransferHook` both the sending and the receiving wallet must be on the vault allowlist
+    /// Both wallets' reward checkpoints are then updated by the vault program
+    #[instruction(discriminator = ExecuteInstruction::SPL_DISCRIMINATOR_SLICE)]
+    pub fn transfer_hook(ctx: Context<TransferHook>, amount: u64) -> Result<()> {
+        // Only accept calls made by Token-2022 in the middle of a transfer
+        require!(
+            is_transferring(&ctx.accounts.source_token.to_account_info())?,
+            ShareTransferHookError::NotTransferring
+        );
+
+        if ctx.accounts.vault.share_transfer_mode == ShareTransferMode::TransferHook {
+            require!(is_allowlisted(&ctx.accounts.source_allowlist)?, ShareTransferHookError::SenderNotAllowed);
+            require!(
+                is_allowlisted(&ctx.accounts.destination_allowlist)?,
+                ShareTransferHookError::RecipientNotAllowed
+            );
+
+            msg!(
+                "Share transfer of {} allowed: {} -> {}",
+                amount,
+                ctx.accounts.source_token.owner,
+                ctx.accounts.destination_token.owner
+            );
+        }
+
+        let checkpoint_ctx = CpiContext::new(
+            ctx.accounts.vault_program.to_account_info(),
+            CheckpointShareTransfer {
+                source_token: ctx.accounts.source_token.to_account_info(),
+                destination_token: ctx.accounts.destination_token.to_account_info(),
+                vault: ctx.accounts.vault.to_account_info(),
+                reward_campaign: ctx.accounts.reward_campaign.to_account_info(),
+                source_checkpoint: ctx.accounts.source_checkpoint.to_account_info(),
+                destination_checkpoint: ctx.accounts.destination_checkpoint.to_account_info(),
+            },
+        );
+        vault_program::cpi::checkpoint_share_transfer(checkpoint_ctx, amount)?;
+
+        Ok(())
+    }
+}
+
+/// Whether an allowlist entry exists and allows its wallet, wallets never allowlisted have no entry
+fn is_allowlisted(allowlist_entry: &AccountInfo) -> Result<bool> {
+    if *allowlist_entry.owner != vault_program::ID {
+        return Ok(false);
+    }
+
+    Ok(AllowlistEntry::try_deserialize(&mut &allowlist_entry.try_borrow_data()?[..])?.allowed)
+}
@@ -0,0 +1,117 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program::{get_return_data, invoke_signed},
+};
+
+use crate::errors::VaultError;
+
+/// Discriminator of the adapter `strategy_deposit(amount: u64)` instruction (`sha256("global:strategy_deposit")[..8]`)
+pub const STRATEGY_DEPOSIT_DISCRIMINATOR: [u8; 8] = [231, 58, 49, 174, 119, 19, 230, 74];
+
+/// Discriminator of the adapter `strategy_withdraw(amount: u64)` instruction (`sha256("global:strategy_withdraw")[..8]`)
+pub const STRATEGY_WITHDRAW_DISCRIMINATOR: [u8; 8] = [17, 121, 13, 73, 154, 20, 116, 217];
+
+/// Discriminator of the adapter `strategy_report() -> u64` instruction (`sha256("global:strategy_report")[..8]`)
+pub const STRATEGY_REPORT_DISCRIMINATOR: [u8; 8] = [205, 189, 43, 214, 126, 89, 152, 43];
+
+/// Accounts every strategy adapter instruction receives first, in this order
+/// The strategy-specific accounts registered with `add_strategy` follow as remaining accounts
+///
+/// The vault PDA never signs a strategy CPI, so a strategy cannot use it to move or approve funds of the vault
+/// token account. Calls that move funds are signed by the vault's `Strategy` registry entry instead, a PDA that has
+/// no authority over any vault account, so strategies can tell them apart from anyone else's calls
+///
+/// - `strategy_deposit` is called once the vault moved `amount` to the strategy token account itself
+/// - `strategy_withdraw` moves `amount` from the strategy token account back to the vault token account
+/// - `strategy_report` returns the current value of the vault's position, in deposit tokens, as return data;
+///   it is called unsigned with read-only token accounts
+pub struct StrategyAdapter<'a, 'info> {
+    pub strategy_program: AccountInfo<'info>,
+    pub strategy: AccountInfo<'info>,
+    pub vault: AccountInfo<'info>,
+    pub vault_token_account: AccountInfo<'info>,
+    pub strategy_token_account: AccountInfo<'info>,
+    pub deposit_token_mint: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    /// Strategy-specific accounts registered with the strategy, the remaining accounts must match them
+    pub adapter_accounts: Vec<Pubkey>,
+    pub remaining_accounts: &'a [AccountInfo<'info>],
+}
+
+impl<'info> StrategyAdapter<'_, 'info> {
+    /// Tell the strategy `amount` deposit tokens were allocated to it, signed by the strategy registry entry
+    pub fn deposit(&self, amount: u64, strategy_seeds: &[&[&[u8]]]) -> Result<()> {
+        self.invoke(STRATEGY_DEPOSIT_DISCRIMINATOR, &amount.to_le_bytes(), strategy_seeds)
+    }
+
+    /// Bring `amount` deposit tokens back from the strategy, signed by the strategy registry entry
+    pub fn withdraw(&self, amount: u64, strategy_seeds: &[&[&[u8]]]) -> Result<()> {
+        self.invoke(STRATEGY_WITHDRAW_DISCRIMINATOR, &amount.to_le_bytes(), strategy_seeds)
+    }
+
+    /// Ask the strategy for the current value of the vault's position, without signing
+    pub fn report(&self) -> Result<u64> {
+        self.invoke(STRATEGY_REPORT_DISCRIMINATOR, &[], &[])?;
+
+        let (program_id, data) = get_return_data().ok_or(VaultError::InvalidStrategyReport)?;
+        require_keys_eq!(program_id, self.strategy_program.key(), VaultError::InvalidStrategyReport);
+        let value = data
+            .get(..8)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(VaultError::InvalidStrategyReport)?;
+
+        Ok(value)
+    }
+
+    fn invoke(&self, discriminator: [u8; 8], args: &[u8], strategy_seeds: &[&[&[u8]]]) -> Result<()> {
+        // Only the accounts the authority registered reach the strategy, whoever builds the transaction
+        require!(
+            self.remaining_accounts.len() == self.adapter_accounts.len()
+                && self.remaining_accounts
+                    .iter()
+                    .zip(&self.adapter_accounts)
+                    .all(|(account, registered)| account.key() == *registered),
+            VaultError::InvalidStrategyAccounts
+        );
+
+        let mut data = discriminator.to_vec();
+        data.extend_from_slice(args);
+
+        let signed = !strategy_seeds.is_empty();
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.strategy.key(), signed),
+            AccountMeta::new_readonly(self.vault.key(), false),
+            AccountMeta { pubkey: self.vault_token_account.key(), is_signer: false, is_writable: signed },
+            AccountMeta { pubkey: self.strategy_token_account.key(), is_signer: false, is_writable: signed },
+            AccountMeta::new_readonly(self.deposit_token_mint.key(), false),
+            AccountMeta::new_readonly(self.token_program.key(), false),
+        ];
+        let mut account_infos = vec![
+            self.strategy.clone(),
+            self.vault.clone(),
+            self.vault_token_account.clone(),
+            self.strategy_token_account.clone(),
+            self.deposit_token_mint.clone(),
+            self.token_program.clone(),
+        ];
+        for account in self.remaining_accounts {
+            // Signatures of the transaction's signers are never forwarded
+            accounts.push(AccountMeta {
+                pubkey: account.key(),
+                is_signer: false,
+                is_writable: account.is_writable && signed,
+            });
+            account_infos.push(account.clone());
+        }
+        account_infos.push(self.strategy_program.clone());
+
+        let ix = Instruction {
+            program_id: self.strategy_program.key(),
+            accounts,
+            data,
+        };
+        invoke_signed(&ix, &account_infos, strategy_seeds).map_err(Into::into)
+    }
+}
@@ -4,8 +4,43 @@ pub const RATE_PRECISION: u64 = 1_000_000_000;
 /// Initial rate: 1 share = 1 token (1e9 precision)
 pub const INITIAL_RATE: u64 = RATE_PRECISION;
 
+/// Virtual shares added to `total_shares` in rate calculations, against first-depositor share inflation
+pub const VIRTUAL_SHARES: u64 = 1_000_000;
 
+/// Virtual assets added to the vault assets in rate calculations, equal to `VIRTUAL_SHARES` so an empty vault prices at `INITIAL_RATE`
+pub const VIRTUAL_ASSETS: u64 = 1_000_000;
 
+/// Maximum length of the IOU token name
+pub const MAX_METADATA_NAME_LEN: usize = 32;
 
+/// Maximum length of the IOU token symbol
+pub const MAX_METADATA_SYMBOL_LEN: usize = 10;
 
+/// Maximum length of the IOU token metadata URI
+pub const MAX_METADATA_URI_LEN: usize = 200;
 
+/// Seconds per year used by the Token-2022 interest-bearing extension
+pub const SECONDS_PER_YEAR: i64 = 31_556_736;
+
+/// Shortest time since the IOU mint initialization the display rate is annualised over, in seconds
+/// Annualising a shorter window saturates the rate the interest-bearing extension accepts, so the previous display
+/// rate is kept until then
+pub const MIN_IOU_DISPLAY_RATE_WINDOW: i64 = 7 * 86_400;
+
+/// Basis points in 100%
+pub const BASIS_POINTS: i64 = 10_000;
+
+/// Largest loss a single `report_loss` can realise, in basis points of `total_deposits`
+pub const MAX_LOSS_REPORT_BPS: i64 = 2_000;
+
+/// Largest gap `audit` accepts between the stored rate and the rate backed by the vault totals, in basis points
+pub const AUDIT_RATE_TOLERANCE_BPS: i64 = 1;
+
+/// Prefix of every signed deposit intent message, so the signature cannot be replayed as another message
+pub const DEPOSIT_INTENT_DOMAIN: &[u8] = b"vault-program:deposit-intent:v1";
+
+/// Largest number of strategy-specific accounts a strategy can register for its adapter calls
+pub const MAX_STRATEGY_ACCOUNTS: usize = 4;
+
+/// Precision of the reward campaign `reward_per_share` accumulator
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000;
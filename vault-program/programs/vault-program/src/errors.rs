@@ -16,5 +16,99 @@ pub enum VaultError {
     Unauthorized,
     #[msg("A pending withdrawal request already exists. Claim it first.")]
     PendingWithdrawalExists,
+    #[msg("Epoch duration must be greater than zero")]
+    InvalidEpochDuration,
+    #[msg("A crank bounty is configured but the accounts needed to pay it are missing")]
+    MissingBountyAccount,
+    #[msg("Shares minted are below the requested minimum")]
+    SharesBelowMinimum,
+    #[msg("Tokens to receive are below the requested minimum")]
+    TokensBelowMinimum,
+    #[msg("Transaction deadline has passed")]
+    DeadlineExceeded,
+    #[msg("Token metadata field is too long")]
+    MetadataTooLong,
+    #[msg("The vault's IOU mint and token program are required to update its display rate")]
+    MissingIouMintAccount,
+    #[msg("A transfer hook program is required for transferable and transfer hook share modes")]
+    MissingTransferHookProgram,
+    #[msg("This vault does not hold wrapped SOL")]
+    NotNativeSolVault,
+    #[msg("NAV oracle staleness and confidence limits must be greater than zero")]
+    InvalidNavOracleConfig,
+    #[msg("No NAV oracle is configured for this vault")]
+    NavOracleNotConfigured,
+    #[msg("The rate is driven by the NAV oracle, use sync_nav")]
+    NavOracleEnabled,
+    #[msg("NAV price must be positive and fully verified")]
+    InvalidNavPrice,
+    #[msg("NAV price is too old")]
+    StaleNavPrice,
+    #[msg("NAV price confidence interval is too wide")]
+    NavConfidenceTooWide,
+    #[msg("Strategy program must be an executable program")]
+    InvalidStrategyProgram,
+    #[msg("Strategy still holds deployed assets")]
+    StrategyHasDeployedAssets,
+    #[msg("Strategy moved a different amount than requested")]
+    StrategyTransferMismatch,
+    #[msg("Amount exceeds the assets deployed to the strategy")]
+    InsufficientDeployedAssets,
+    #[msg("Strategy did not report its position value")]
+    InvalidStrategyReport,
+    #[msg("Idle buffer must be at most 100%")]
+    InvalidIdleBuffer,
+    #[msg("Allocation would leave less idle liquidity than pending withdrawals and the idle buffer require")]
+    IdleBufferBreached,
+    #[msg("Not enough idle liquidity to pay the claim, pass a strategy to deallocate from")]
+    InsufficientIdleLiquidity,
+    #[msg("Reported loss exceeds the assets deployed to the strategy")]
+    LossExceedsDeployedAssets,
+    #[msg("Reported loss exceeds the per-report limit")]
+    LossReportTooLarge,
+    #[msg("Surplus recipient must be a deposit token account")]
+    InvalidSurplusRecipient,
+    #[msg("Surplus recipient account is required")]
+    MissingSurplusRecipient,
+    #[msg("Vault token balance is below the accounted idle assets and pending withdrawals")]
+    VaultBalanceShortfall,
+    #[msg("Vault token balance has no surplus to sync")]
+    NoSurplusToSync,
+    #[msg("IOU mint supply does not match the vault total shares")]
+    ShareSupplyMismatch,
+    #[msg("Rate does not match the vault total deposits and shares")]
+    RateMismatch,
+    #[msg("Operator is not approved by the owner")]
+    OperatorNotApproved,
+    #[msg("Owner has not delegated enough IOU shares to the vault for operator requests")]
+    InsufficientShareDelegation,
+    #[msg("Deposit intent is not for this vault")]
+    InvalidIntent,
+    #[msg("Deposit intent nonce does not match the next expected nonce")]
+    InvalidIntentNonce,
+    #[msg("Deposit intent is not signed by the user in the preceding ed25519 instruction")]
+    InvalidIntentSignature,
+    #[msg("User has not delegated enough tokens to the vault for the deposit intent")]
+    InsufficientTokenDelegation,
+    #[msg("The vault has a reward campaign, its account and the user's reward checkpoint are required")]
+    MissingRewardCheckpoint,
+    #[msg("Reward campaign is not the vault's active campaign")]
+    RewardCampaignInactive,
+    #[msg("Rewards cannot be funded while the vault has no shares")]
+    NoSharesForRewards,
+    #[msg("NAV rate must be synced with sync_nav in the same slot before deposits and withdrawal requests")]
+    NavNotSynced,
+    #[msg("Strategy-specific accounts must match the accounts registered with the strategy")]
+    InvalidStrategyAccounts,
+    #[msg("Too many strategy-specific accounts")]
+    TooManyStrategyAccounts,
+    #[msg("Strategy report changed the vault token account balance")]
+    StrategyReportMovedFunds,
+    #[msg("NAV price account is not owned by the configured oracle program")]
+    NavOracleOwnerMismatch,
+    #[msg("NAV price account publishes another price feed")]
+    NavFeedMismatch,
+    #[msg("Share transfer checkpoints can only be written by the share transfer hook during an IOU transfer")]
+    NotTransferring,
 }
 
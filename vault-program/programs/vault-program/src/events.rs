@@ -0,0 +1,175 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{EpochMode, ShareTransferMode};
+
+/// A vault and its IOU mint were created
+#[event]
+pub struct VaultInitialized {
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub deposit_token_mint: Pubkey,
+    pub iou_token_mint: Pubkey,
+    pub vault_token_account: Pubkey,
+    pub epoch_mode: EpochMode,
+    pub epoch_duration: i64,
+    pub rate: u64,
+    pub interest_bearing_iou: bool,
+    pub share_transfer_mode: ShareTransferMode,
+}
+
+/// Tokens were deposited and shares minted (`deposit`, `deposit_sol` or `deposit_for`)
+#[event]
+pub struct Deposited {
+    pub vault: Pubkey,
+    /// Wallet that funded the deposit
+    pub user: Pubkey,
+    /// Wallet that received the shares, the user itself except for `deposit_for`
+    pub beneficiary: Pubkey,
+    /// Tokens sent by the user
+    pub amount: u64,
+    /// Tokens credited to the vault after any Token-2022 transfer fee
+    pub amount_received: u64,
+    pub shares_minted: u64,
+    pub rate: u64,
+    pub total_deposits: u64,
+    pub total_shares: u64,
+}
+
+/// Shares were burned and their tokens locked in a withdrawal request
+#[event]
+pub struct WithdrawalRequested {
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    /// Wallet the claim will pay out to
+    pub receiver: Pubkey,
+    pub shares_amount: u64,
+    pub tokens_to_receive: u64,
+    pub rate: u64,
+    pub request_epoch: u64,
+    pub claimable_epoch: u64,
+    pub total_shares: u64,
+}
+
+/// A withdrawal request was paid out (`claim_withdrawal` or `claim_withdrawal_sol`)
+#[event]
+pub struct WithdrawalClaimed {
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    /// Wallet the tokens were paid to
+    pub receiver: Pubkey,
+    /// Tokens sent by the vault, after any loss haircut
+    pub tokens_claimed: u64,
+    /// Part of `tokens_claimed` withheld as Token-2022 transfer fee
+    pub transfer_fee: u64,
+    pub claimable_epoch: u64,
+    pub current_epoch: u64,
+    pub total_deposits: u64,
+}
+
+/// Rewards were added and the rate recalculated
+#[event]
+pub struct RateIncreased {
+    pub vault: Pubkey,
+    /// Tokens sent by the authority
+    pub additional_tokens: u64,
+    /// Tokens credited to the vault after any Token-2022 transfer fee
+    pub tokens_received: u64,
+    pub rate_before: u64,
+    pub rate_after: u64,
+    pub total_deposits: u64,
+    pub total_shares: u64,
+}
+
+/// The vault moved to a new epoch (`advance_epoch` or `force_advance_epoch`)
+#[event]
+pub struct EpochAdvanced {
+    pub vault: Pubkey,
+    pub previous_epoch: u64,
+    pub current_epoch: u64,
+    /// Whether the authority forced the advance
+    pub forced: bool,
+    /// Crank bounty paid for this advance
+    pub bounty_paid: u64,
+    pub timestamp: i64,
+}
+
+/// The rate was recalculated outside `increase_rate` and loss reporting
+#[event]
+pub struct RateChanged {
+    pub vault: Pubkey,
+    pub source: RateChangeSource,
+    pub old_rate: u64,
+    pub new_rate: u64,
+    pub total_deposits: u64,
+    pub total_shares: u64,
+}
+
+/// Instruction that moved the rate in a `RateChanged` event
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RateChangeSource {
+    /// Strategy profit realised by `harvest`
+    Harvest,
+    /// Direct transfer folded in by `sync`
+    Sync,
+    /// NAV price read by `sync_nav`
+    SyncNav,
+}
+
+/// A strategy loss was realised and spread pro rata over share holders and pending withdrawals
+#[event]
+pub struct LossReported {
+    pub vault: Pubkey,
+    /// Strategy program the loss was realised on
+    pub strategy_program: Pubkey,
+    /// Signer that realised the loss (`report_loss` caller or `harvest` authority)
+    pub reporter: Pubkey,
+    pub loss: u64,
+    /// Part of the loss taken from pending withdrawal requests
+    pub pending_withdrawals_haircut: u64,
+    pub rate_before: u64,
+    pub rate_after: u64,
+    pub total_deposits: u64,
+    /// Vault withdrawal loss index after the loss, claims pay `tokens_to_receive * index / request index`
+    pub withdrawal_loss_index: u64,
+}
+
+/// A withdrawal was claimed for less than it was requested because of losses reported while it was pending
+#[event]
+pub struct WithdrawalHaircut {
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    /// Tokens locked at request time
+    pub tokens_requested: u64,
+    /// Tokens paid by the claim
+    pub tokens_paid: u64,
+}
+
+/// Reward tokens were added to a campaign (`fund_rewards`)
+#[event]
+pub struct RewardsFunded {
+    pub vault: Pubkey,
+    pub reward_campaign: Pubkey,
+    pub amount: u64,
+    /// Tokens credited to the campaign after any Token-2022 transfer fee
+    pub amount_received: u64,
+    /// Campaign accumulator after the funding, scaled by REWARD_PRECISION
+    pub reward_per_share: u128,
+    /// Vault shares the funding was split over
+    pub total_shares: u64,
+}
+
+/// A user claimed their accrued campaign rewards (`claim_rewards`)
+#[event]
+pub struct RewardsClaimed {
+    pub vault: Pubkey,
+    pub reward_campaign: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub reward_per_share: u128,
+}
+
+
+
+
+
+
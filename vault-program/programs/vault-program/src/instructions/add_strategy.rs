@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+
+use crate::errors::VaultError;
+use crate::state::{Strategy, Vault};
+
+/// Strategy-specific accounts the adapter calls need are passed as remaining accounts and registered with the strategy
+#[derive(Accounts)]
+pub struct AddStrategy<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == vault.authority @ VaultError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Registry entry approving the strategy program for this vault (PDA per vault and program)
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Strategy::INIT_SPACE,
+        seeds = [b"strategy", vault.key().as_ref(), strategy_program.key().as_ref()],
+        bump
+    )]
+    pub strategy: Account<'info, Strategy>,
+
+    /// CHECK: only its address is recorded, it must implement the strategy adapter interface
+    #[account(constraint = strategy_program.executable @ VaultError::InvalidStrategyProgram)]
+    pub strategy_program: UncheckedAccount<'info>,
+
+    /// Token account the strategy receives allocations in
+    #[account(token::mint = vault.deposit_token_mint)]
+    pub strategy_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveStrategy<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == vault.authority @ VaultError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"strategy", vault.key().as_ref(), strategy.strategy_program.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.deployed_assets == 0 @ VaultError::StrategyHasDeployedAssets
+    )]
+    pub strategy: Account<'info, Strategy>,
+}
+
+
+
+
+
+
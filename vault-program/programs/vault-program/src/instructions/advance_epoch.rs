@@ -1,8 +1,13 @@
 use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::Token2022,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
 
 use crate::errors::VaultError;
-use crate::state::Vault;
+use crate::state::{CrankBounty, Vault};
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct AdvanceEpoch<'info> {
     #[account(
@@ -11,8 +16,51 @@ pub struct AdvanceEpoch<'info> {
         bump = vault.bump
     )]
     pub vault: Account<'info, Vault>,
+
+    /// Caller advancing the epoch, receives the crank bounty if one is configured
+    #[account(mut)]
+    pub cranker: Option<Signer<'info>>,
+
+    /// Crank bounty configuration and lamport reserve
+    #[account(
+        mut,
+        seeds = [b"crank_bounty", vault.key().as_ref()],
+        bump = crank_bounty.bump
+    )]
+    pub crank_bounty: Option<Account<'info, CrankBounty>>,
+
+    /// Reward token account funding deposit token bounties
+    #[account(
+        mut,
+        constraint = bounty_token_account.mint == vault.deposit_token_mint @ VaultError::MissingBountyAccount
+    )]
+    pub bounty_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Cranker's token account receiving deposit token bounties
+    #[account(
+        mut,
+        constraint = cranker_token_account.mint == vault.deposit_token_mint @ VaultError::MissingBountyAccount
+    )]
+    pub cranker_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Deposit token mint, needed for deposit token bounties
+    #[account(address = vault.deposit_token_mint)]
+    pub deposit_token_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    /// IOU token mint, required when the vault uses the interest-bearing display mode
+    #[account(
+        mut,
+        seeds = [b"vault", vault.key().as_ref()],
+        bump = vault.iou_mint_bump
+    )]
+    pub iou_token_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    pub iou_token_program: Option<Program<'info, Token2022>>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct ForceAdvanceEpoch<'info> {
     #[account(
@@ -26,6 +74,16 @@ pub struct ForceAdvanceEpoch<'info> {
         bump = vault.bump
     )]
     pub vault: Account<'info, Vault>,
+
+    /// IOU token mint, required when the vault uses the interest-bearing display mode
+    #[account(
+        mut,
+        seeds = [b"vault", vault.key().as_ref()],
+        bump = vault.iou_mint_bump
+    )]
+    pub iou_token_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    pub iou_token_program: Option<Program<'info, Token2022>>,
 }
 
 
@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+use crate::state::Vault;
+
+#[derive(Accounts)]
+pub struct Audit<'info> {
+    #[account(
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Vault's token account
+    #[account(address = vault.vault_token_account)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// IOU token mint
+    #[account(
+        seeds = [b"vault", vault.key().as_ref()],
+        bump = vault.iou_mint_bump
+    )]
+    pub iou_token_mint: InterfaceAccount<'info, Mint>,
+}
+
+
+
+
+
+
@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+
+use crate::errors::VaultError;
+use crate::state::Vault;
+
+/// Accounts passed by the share transfer hook while Token-2022 moves IOU shares
+#[derive(Accounts)]
+pub struct CheckpointShareTransfer<'info> {
+    #[account(token::mint = vault.iou_token_mint)]
+    pub source_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(token::mint = vault.iou_token_mint)]
+    pub destination_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Vault's active reward campaign (default pubkey when there is none)
+    /// CHECK: only checked by address, loaded by the handler while the vault has a campaign
+    #[account(address = vault.reward_campaign @ VaultError::RewardCampaignInactive)]
+    pub reward_campaign: UncheckedAccount<'info>,
+
+    /// Sending wallet's checkpoint in the campaign, skipped when the wallet has none
+    /// CHECK: PDA checked by seeds, loaded by the handler when it exists
+    #[account(
+        mut,
+        seeds = [b"reward_checkpoint", reward_campaign.key().as_ref(), source_token.owner.as_ref()],
+        bump
+    )]
+    pub source_checkpoint: UncheckedAccount<'info>,
+
+    /// Receiving wallet's checkpoint in the campaign, skipped when the wallet has none
+    /// CHECK: PDA checked by seeds, loaded by the handler when it exists
+    #[account(
+        mut,
+        seeds = [b"reward_checkpoint", reward_campaign.key().as_ref(), destination_token.owner.as_ref()],
+        bump
+    )]
+    pub destination_checkpoint: UncheckedAccount<'info>,
+}
+
+
+
+
+
+
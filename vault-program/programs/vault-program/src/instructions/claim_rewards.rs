@@ -0,0 +1,80 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_2022::Token2022,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::state::{RewardCampaign, RewardCheckpoint, Vault};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Reward campaign, active or ended
+    #[account(
+        mut,
+        seeds = [b"reward_campaign", vault.key().as_ref(), reward_mint.key().as_ref()],
+        bump = reward_campaign.bump
+    )]
+    pub reward_campaign: Account<'info, RewardCampaign>,
+
+    /// User's checkpoint in the campaign (PDA per campaign and user), created on the first claim
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + RewardCheckpoint::INIT_SPACE,
+        seeds = [b"reward_checkpoint", reward_campaign.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub reward_checkpoint: Account<'info, RewardCheckpoint>,
+
+    /// User's IOU token account, its balance earns the rewards
+    #[account(
+        associated_token::mint = vault.iou_token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = iou_token_program
+    )]
+    pub user_iou_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Campaign's reward token account
+    #[account(
+        mut,
+        address = reward_campaign.reward_token_account
+    )]
+    pub campaign_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Reward token mint
+    #[account(address = reward_campaign.reward_mint)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    /// User's reward token account (destination)
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = reward_mint,
+        associated_token::authority = user,
+        associated_token::token_program = reward_token_program
+    )]
+    pub user_reward_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub reward_token_program: Interface<'info, TokenInterface>,
+    pub iou_token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+
+
+
+
+
+
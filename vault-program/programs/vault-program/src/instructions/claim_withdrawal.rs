@@ -1,9 +1,11 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 
 use crate::errors::VaultError;
-use crate::state::{Vault, WithdrawalRequest};
+use crate::state::{Strategy, UserPosition, Vault, WithdrawalRequest};
+use crate::utils::ClaimPayout;
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct ClaimWithdrawal<'info> {
     #[account(mut)]
@@ -25,26 +27,69 @@ pub struct ClaimWithdrawal<'info> {
     )]
     pub withdrawal_request: Account<'info, WithdrawalRequest>,
 
-    /// User's token account (destination)
+    /// User's position in the vault
+    #[account(
+        mut,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump = user_position.bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    /// Receiver's token account (destination)
     #[account(
         mut,
         associated_token::mint = deposit_token_mint,
-        associated_token::authority = user
+        associated_token::authority = withdrawal_request.receiver,
+        associated_token::token_program = token_program
     )]
-    pub user_token_account: Account<'info, TokenAccount>,
+    pub receiver_token_account: InterfaceAccount<'info, TokenAccount>,
 
     /// Vault's token account
     #[account(
         mut,
         address = vault.vault_token_account
     )]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
 
     /// Deposit token mint
     #[account(address = vault.deposit_token_mint)]
-    pub deposit_token_mint: Account<'info, Mint>,
+    pub deposit_token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Strategy to deallocate from when the vault token account cannot pay the claim
+    #[account(
+        mut,
+        seeds = [b"strategy", vault.key().as_ref(), strategy.strategy_program.as_ref()],
+        bump = strategy.bump
+    )]
+    pub strategy: Option<Account<'info, Strategy>>,
+
+    /// CHECK: checked against the strategy registry entry by the handler
+    pub strategy_program: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: checked against the strategy registry entry by the handler
+    #[account(mut)]
+    pub strategy_token_account: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
 
-    pub token_program: Program<'info, Token>,
+impl<'info> ClaimWithdrawal<'info> {
+    /// Accounts paying the request to the receiver's token account
+    pub fn claim_payout<'a>(&'a mut self, remaining_accounts: &'a [AccountInfo<'info>]) -> ClaimPayout<'a, 'info> {
+        ClaimPayout {
+            vault: &mut self.vault,
+            withdrawal_request: &mut self.withdrawal_request,
+            owner_position: &mut self.user_position,
+            vault_token_account: &mut self.vault_token_account,
+            destination: &mut self.receiver_token_account,
+            deposit_token_mint: &self.deposit_token_mint,
+            token_program: &self.token_program,
+            strategy: self.strategy.as_mut(),
+            strategy_program: self.strategy_program.as_ref(),
+            strategy_token_account: self.strategy_token_account.as_ref(),
+            remaining_accounts,
+        }
+    }
 }
 
 
@@ -0,0 +1,110 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::errors::VaultError;
+use crate::state::{Operator, Strategy, UserPosition, Vault, WithdrawalRequest};
+use crate::utils::ClaimPayout;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimWithdrawalFor<'info> {
+    /// Approved operator
+    pub operator: Signer<'info>,
+
+    /// CHECK: only used to derive the owner's accounts, the operator approval ties it to the operator
+    pub owner: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Owner's approval of the operator
+    #[account(
+        seeds = [b"operator", vault.key().as_ref(), owner.key().as_ref(), operator.key().as_ref()],
+        bump = operator_approval.bump,
+        constraint = operator_approval.approved @ VaultError::OperatorNotApproved
+    )]
+    pub operator_approval: Account<'info, Operator>,
+
+    /// Owner's withdrawal request account
+    #[account(
+        mut,
+        seeds = [b"withdrawal", vault.key().as_ref(), owner.key().as_ref()],
+        bump = withdrawal_request.bump,
+        constraint = withdrawal_request.user == owner.key() @ VaultError::Unauthorized
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+
+    /// Owner's position in the vault
+    #[account(
+        mut,
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump = owner_position.bump
+    )]
+    pub owner_position: Account<'info, UserPosition>,
+
+    /// Receiver's token account (destination)
+    #[account(
+        mut,
+        associated_token::mint = deposit_token_mint,
+        associated_token::authority = withdrawal_request.receiver,
+        associated_token::token_program = token_program
+    )]
+    pub receiver_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Vault's token account
+    #[account(
+        mut,
+        address = vault.vault_token_account
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Deposit token mint
+    #[account(address = vault.deposit_token_mint)]
+    pub deposit_token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Strategy to deallocate from when the vault token account cannot pay the claim
+    #[account(
+        mut,
+        seeds = [b"strategy", vault.key().as_ref(), strategy.strategy_program.as_ref()],
+        bump = strategy.bump
+    )]
+    pub strategy: Option<Account<'info, Strategy>>,
+
+    /// CHECK: checked against the strategy registry entry by the handler
+    pub strategy_program: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: checked against the strategy registry entry by the handler
+    #[account(mut)]
+    pub strategy_token_account: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ClaimWithdrawalFor<'info> {
+    /// Accounts paying the request to the receiver's token account
+    pub fn claim_payout<'a>(&'a mut self, remaining_accounts: &'a [AccountInfo<'info>]) -> ClaimPayout<'a, 'info> {
+        ClaimPayout {
+            vault: &mut self.vault,
+            withdrawal_request: &mut self.withdrawal_request,
+            owner_position: &mut self.owner_position,
+            vault_token_account: &mut self.vault_token_account,
+            destination: &mut self.receiver_token_account,
+            deposit_token_mint: &self.deposit_token_mint,
+            token_program: &self.token_program,
+            strategy: self.strategy.as_mut(),
+            strategy_program: self.strategy_program.as_ref(),
+            strategy_token_account: self.strategy_token_account.as_ref(),
+            remaining_accounts,
+        }
+    }
+}
+
+
+
+
+
+
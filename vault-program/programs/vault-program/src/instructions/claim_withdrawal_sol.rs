@@ -0,0 +1,108 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::errors::VaultError;
+use crate::state::{Strategy, UserPosition, Vault, WithdrawalRequest};
+use crate::utils::{is_native_mint, ClaimPayout};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimWithdrawalSol<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump,
+        constraint = is_native_mint(&vault.deposit_token_mint) @ VaultError::NotNativeSolVault
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Withdrawal request account
+    #[account(
+        mut,
+        seeds = [b"withdrawal", vault.key().as_ref(), user.key().as_ref()],
+        bump = withdrawal_request.bump,
+        constraint = withdrawal_request.user == user.key() @ VaultError::Unauthorized
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+
+    /// User's position in the vault
+    #[account(
+        mut,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump = user_position.bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    /// CHECK: wallet the request pays out to, only receives the unwrapped lamports
+    #[account(mut, address = withdrawal_request.receiver)]
+    pub receiver: UncheckedAccount<'info>,
+
+    /// Temporary wSOL account the claim is moved to, closed in the same instruction to unwrap it to the receiver
+    #[account(
+        init,
+        payer = user,
+        seeds = [b"sol_claim", vault.key().as_ref(), user.key().as_ref()],
+        bump,
+        token::mint = deposit_token_mint,
+        token::authority = vault,
+        token::token_program = token_program
+    )]
+    pub unwrap_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Vault's wSOL token account
+    #[account(
+        mut,
+        address = vault.vault_token_account
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Native (wSOL) mint
+    #[account(address = vault.deposit_token_mint)]
+    pub deposit_token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Strategy to deallocate from when the vault token account cannot pay the claim
+    #[account(
+        mut,
+        seeds = [b"strategy", vault.key().as_ref(), strategy.strategy_program.as_ref()],
+        bump = strategy.bump
+    )]
+    pub strategy: Option<Account<'info, Strategy>>,
+
+    /// CHECK: checked against the strategy registry entry by the handler
+    pub strategy_program: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: checked against the strategy registry entry by the handler
+    #[account(mut)]
+    pub strategy_token_account: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimWithdrawalSol<'info> {
+    /// Accounts paying the request to the temporary wSOL account, which is then closed to the receiver
+    pub fn claim_payout<'a>(&'a mut self, remaining_accounts: &'a [AccountInfo<'info>]) -> ClaimPayout<'a, 'info> {
+        ClaimPayout {
+            vault: &mut self.vault,
+            withdrawal_request: &mut self.withdrawal_request,
+            owner_position: &mut self.user_position,
+            vault_token_account: &mut self.vault_token_account,
+            destination: &mut self.unwrap_token_account,
+            deposit_token_mint: &self.deposit_token_mint,
+            token_program: &self.token_program,
+            strategy: self.strategy.as_mut(),
+            strategy_program: self.strategy_program.as_ref(),
+            strategy_token_account: self.strategy_token_account.as_ref(),
+            remaining_accounts,
+        }
+    }
+}
+
+
+
+
+
+
@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::errors::VaultError;
+use crate::state::{CrankBounty, Vault};
+
+#[derive(Accounts)]
+pub struct ConfigureCrankBounty<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == vault.authority @ VaultError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Crank bounty configuration (PDA per vault), also holds lamport bounties
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + CrankBounty::INIT_SPACE,
+        seeds = [b"crank_bounty", vault.key().as_ref()],
+        bump
+    )]
+    pub crank_bounty: Account<'info, CrankBounty>,
+
+    /// Reward token account holding deposit token bounties
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = deposit_token_mint,
+        associated_token::authority = crank_bounty,
+        associated_token::token_program = token_program
+    )]
+    pub bounty_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Deposit token mint
+    #[account(address = vault.deposit_token_mint)]
+    pub deposit_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+
+
+
+
+
@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::VaultError;
+use crate::state::Vault;
+
+#[derive(Accounts)]
+pub struct ConfigureNavOracle<'info> {
+    #[account(
+        constraint = authority.key() == vault.authority @ VaultError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Price account with a Pyth `PriceUpdateV2` layout, omitted to go back to `increase_rate`
+    /// CHECK: owner, layout, discriminator and feed are checked by the handler
+    pub nav_oracle: Option<UncheckedAccount<'info>>,
+}
+
+
+
+
+
+
@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+
+use crate::errors::VaultError;
+use crate::state::Vault;
+
+#[derive(Accounts)]
+pub struct ConfigureSurplusRecipient<'info> {
+    #[account(
+        constraint = authority.key() == vault.authority @ VaultError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Deposit token account receiving the surplus found by `sync`, omitted to fold it into the rate
+    #[account(
+        constraint = surplus_recipient.mint == vault.deposit_token_mint @ VaultError::InvalidSurplusRecipient
+    )]
+    pub surplus_recipient: Option<InterfaceAccount<'info, TokenAccount>>,
+}
+
+
+
+
+
+
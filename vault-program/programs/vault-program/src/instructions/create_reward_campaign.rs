@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::errors::VaultError;
+use crate::state::{RewardCampaign, Vault};
+
+#[derive(Accounts)]
+pub struct CreateRewardCampaign<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == vault.authority @ VaultError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Reward campaign (PDA per vault and reward mint)
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + RewardCampaign::INIT_SPACE,
+        seeds = [b"reward_campaign", vault.key().as_ref(), reward_mint.key().as_ref()],
+        bump
+    )]
+    pub reward_campaign: Account<'info, RewardCampaign>,
+
+    /// Reward token mint
+    #[account(mint::token_program = reward_token_program)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    /// Campaign's reward token account
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = reward_mint,
+        associated_token::authority = reward_campaign,
+        associated_token::token_program = reward_token_program
+    )]
+    pub campaign_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub reward_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+
+
+
+
+
+
@@ -1,11 +1,14 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount},
+    token_2022::Token2022,
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
-use crate::state::Vault;
+use crate::state::{RewardCampaign, RewardCheckpoint, UserPosition, Vault};
+use crate::utils::{DepositShares, RewardAccounts};
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Deposit<'info> {
     #[account(mut)]
@@ -22,29 +25,55 @@ pub struct Deposit<'info> {
     #[account(
         mut,
         associated_token::mint = deposit_token_mint,
-        associated_token::authority = user
+        associated_token::authority = user,
+        associated_token::token_program = token_program
     )]
-    pub user_token_account: Account<'info, TokenAccount>,
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
 
     /// User's IOU token account (destination for shares)
     #[account(
         init_if_needed,
         payer = user,
         associated_token::mint = iou_token_mint,
-        associated_token::authority = user
+        associated_token::authority = user,
+        associated_token::token_program = iou_token_program
     )]
-    pub user_iou_account: Account<'info, TokenAccount>,
+    pub user_iou_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// User's position in the vault (PDA per vault and user)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    /// Vault's active reward campaign, required while the vault has one
+    #[account(address = vault.reward_campaign)]
+    pub reward_campaign: Option<Account<'info, RewardCampaign>>,
+
+    /// User's checkpoint in the active reward campaign (PDA per campaign and user)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + RewardCheckpoint::INIT_SPACE,
+        seeds = [b"reward_checkpoint", vault.reward_campaign.as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub reward_checkpoint: Option<Account<'info, RewardCheckpoint>>,
 
     /// Vault's token account
     #[account(
         mut,
         address = vault.vault_token_account
     )]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
 
     /// Deposit token mint
     #[account(address = vault.deposit_token_mint)]
-    pub deposit_token_mint: Account<'info, Mint>,
+    pub deposit_token_mint: InterfaceAccount<'info, Mint>,
 
     /// IOU token mint
     #[account(
@@ -52,13 +81,36 @@ pub struct Deposit<'info> {
         seeds = [b"vault", vault.key().as_ref()],
         bump = vault.iou_mint_bump
     )]
-    pub iou_token_mint: Account<'info, Mint>,
+    pub iou_token_mint: InterfaceAccount<'info, Mint>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub iou_token_program: Program<'info, Token2022>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
+impl<'info> Deposit<'info> {
+    /// Accounts crediting the deposit, the depositor receives the shares
+    pub fn deposit_shares(&mut self, bumps: &DepositBumps) -> DepositShares<'_, 'info> {
+        DepositShares {
+            vault: &mut self.vault,
+            vault_token_account: &mut self.vault_token_account,
+            iou_token_mint: &self.iou_token_mint,
+            iou_token_program: &self.iou_token_program,
+            payer: self.user.key(),
+            beneficiary: self.user.key(),
+            beneficiary_iou_account: &self.user_iou_account,
+            beneficiary_position: &mut self.user_position,
+            beneficiary_position_bump: bumps.user_position,
+            rewards: RewardAccounts {
+                reward_campaign: self.reward_campaign.as_ref(),
+                reward_checkpoint: self.reward_checkpoint.as_mut(),
+                reward_checkpoint_bump: bumps.reward_checkpoint,
+            },
+        }
+    }
+}
+
 
 
 
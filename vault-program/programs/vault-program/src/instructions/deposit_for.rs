@@ -0,0 +1,122 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_2022::Token2022,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::state::{RewardCampaign, RewardCheckpoint, UserPosition, Vault};
+use crate::utils::{DepositShares, RewardAccounts};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DepositFor<'info> {
+    /// Wallet funding the deposit
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: any wallet, only used as the owner of the IOU account receiving the shares
+    pub beneficiary: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Payer's token account (source)
+    #[account(
+        mut,
+        associated_token::mint = deposit_token_mint,
+        associated_token::authority = payer,
+        associated_token::token_program = token_program
+    )]
+    pub payer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Beneficiary's IOU token account (destination for shares), created by the payer if missing
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = iou_token_mint,
+        associated_token::authority = beneficiary,
+        associated_token::token_program = iou_token_program
+    )]
+    pub beneficiary_iou_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Beneficiary's position in the vault (PDA per vault and user)
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UserPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), beneficiary.key().as_ref()],
+        bump
+    )]
+    pub beneficiary_position: Account<'info, UserPosition>,
+
+    /// Vault's active reward campaign, required while the vault has one
+    #[account(address = vault.reward_campaign)]
+    pub reward_campaign: Option<Account<'info, RewardCampaign>>,
+
+    /// Beneficiary's checkpoint in the active reward campaign (PDA per campaign and user)
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RewardCheckpoint::INIT_SPACE,
+        seeds = [b"reward_checkpoint", vault.reward_campaign.as_ref(), beneficiary.key().as_ref()],
+        bump
+    )]
+    pub reward_checkpoint: Option<Account<'info, RewardCheckpoint>>,
+
+    /// Vault's token account
+    #[account(
+        mut,
+        address = vault.vault_token_account
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Deposit token mint
+    #[account(address = vault.deposit_token_mint)]
+    pub deposit_token_mint: InterfaceAccount<'info, Mint>,
+
+    /// IOU token mint
+    #[account(
+        mut,
+        seeds = [b"vault", vault.key().as_ref()],
+        bump = vault.iou_mint_bump
+    )]
+    pub iou_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub iou_token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> DepositFor<'info> {
+    /// Accounts crediting the deposit, the payer funds it and the beneficiary receives the shares
+    pub fn deposit_shares(&mut self, bumps: &DepositForBumps) -> DepositShares<'_, 'info> {
+        DepositShares {
+            vault: &mut self.vault,
+            vault_token_account: &mut self.vault_token_account,
+            iou_token_mint: &self.iou_token_mint,
+            iou_token_program: &self.iou_token_program,
+            payer: self.payer.key(),
+            beneficiary: self.beneficiary.key(),
+            beneficiary_iou_account: &self.beneficiary_iou_account,
+            beneficiary_position: &mut self.beneficiary_position,
+            beneficiary_position_bump: bumps.beneficiary_position,
+            rewards: RewardAccounts {
+                reward_campaign: self.reward_campaign.as_ref(),
+                reward_checkpoint: self.reward_checkpoint.as_mut(),
+                reward_checkpoint_bump: bumps.reward_checkpoint,
+            },
+        }
+    }
+}
+
+
+
+
+
+
@@ -0,0 +1,107 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_2022::Token2022,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::errors::VaultError;
+use crate::state::{RewardCampaign, RewardCheckpoint, UserPosition, Vault};
+use crate::utils::{is_native_mint, DepositShares, RewardAccounts};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DepositSol<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump,
+        constraint = is_native_mint(&vault.deposit_token_mint) @ VaultError::NotNativeSolVault
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// User's IOU token account (destination for shares)
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = iou_token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = iou_token_program
+    )]
+    pub user_iou_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// User's position in the vault (PDA per vault and user)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    /// Vault's active reward campaign, required while the vault has one
+    #[account(address = vault.reward_campaign)]
+    pub reward_campaign: Option<Account<'info, RewardCampaign>>,
+
+    /// User's checkpoint in the active reward campaign (PDA per campaign and user)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + RewardCheckpoint::INIT_SPACE,
+        seeds = [b"reward_checkpoint", vault.reward_campaign.as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub reward_checkpoint: Option<Account<'info, RewardCheckpoint>>,
+
+    /// Vault's wSOL token account, lamports are sent here and wrapped
+    #[account(
+        mut,
+        address = vault.vault_token_account
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// IOU token mint
+    #[account(
+        mut,
+        seeds = [b"vault", vault.key().as_ref()],
+        bump = vault.iou_mint_bump
+    )]
+    pub iou_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub iou_token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> DepositSol<'info> {
+    /// Accounts crediting the wrapped lamports, the depositor receives the shares
+    pub fn deposit_shares(&mut self, bumps: &DepositSolBumps) -> DepositShares<'_, 'info> {
+        DepositShares {
+            vault: &mut self.vault,
+            vault_token_account: &mut self.vault_token_account,
+            iou_token_mint: &self.iou_token_mint,
+            iou_token_program: &self.iou_token_program,
+            payer: self.user.key(),
+            beneficiary: self.user.key(),
+            beneficiary_iou_account: &self.user_iou_account,
+            beneficiary_position: &mut self.user_position,
+            beneficiary_position_bump: bumps.user_position,
+            rewards: RewardAccounts {
+                reward_campaign: self.reward_campaign.as_ref(),
+                reward_checkpoint: self.reward_checkpoint.as_mut(),
+                reward_checkpoint_bump: bumps.reward_checkpoint,
+            },
+        }
+    }
+}
+
+
+
+
+
+
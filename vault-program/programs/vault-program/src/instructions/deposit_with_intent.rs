@@ -0,0 +1,144 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_2022::Token2022,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+use solana_sdk_ids::sysvar;
+
+use crate::state::{IntentNonce, RewardCampaign, RewardCheckpoint, UserPosition, Vault};
+use crate::utils::{DepositShares, RewardAccounts};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DepositWithIntent<'info> {
+    /// Relayer submitting the intent, pays the fees and any account rent
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    /// CHECK: authorises the deposit through the ed25519 signature of the intent, checked by the handler
+    pub user: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// User's intent nonce (PDA per vault and user)
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        space = 8 + IntentNonce::INIT_SPACE,
+        seeds = [b"intent_nonce", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub intent_nonce: Account<'info, IntentNonce>,
+
+    /// User's token account (source), spent through the user's delegation to the vault delegate
+    #[account(
+        mut,
+        associated_token::mint = deposit_token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: PDA users delegate their allowances to, it signs nothing but delegated burns and transfers
+    #[account(
+        seeds = [b"delegate", vault.key().as_ref()],
+        bump
+    )]
+    pub delegate: UncheckedAccount<'info>,
+
+    /// User's IOU token account (destination for shares)
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        associated_token::mint = iou_token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = iou_token_program
+    )]
+    pub user_iou_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// User's position in the vault (PDA per vault and user)
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        space = 8 + UserPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    /// Vault's active reward campaign, required while the vault has one
+    #[account(address = vault.reward_campaign)]
+    pub reward_campaign: Option<Account<'info, RewardCampaign>>,
+
+    /// User's checkpoint in the active reward campaign (PDA per campaign and user)
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        space = 8 + RewardCheckpoint::INIT_SPACE,
+        seeds = [b"reward_checkpoint", vault.reward_campaign.as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub reward_checkpoint: Option<Account<'info, RewardCheckpoint>>,
+
+    /// Vault's token account
+    #[account(
+        mut,
+        address = vault.vault_token_account
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Deposit token mint
+    #[account(address = vault.deposit_token_mint)]
+    pub deposit_token_mint: InterfaceAccount<'info, Mint>,
+
+    /// IOU token mint
+    #[account(
+        mut,
+        seeds = [b"vault", vault.key().as_ref()],
+        bump = vault.iou_mint_bump
+    )]
+    pub iou_token_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: instructions sysvar, read to find the ed25519 verification of the intent
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub iou_token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> DepositWithIntent<'info> {
+    /// Accounts crediting the deposit, the user who signed the intent funds it and receives the shares
+    pub fn deposit_shares(&mut self, bumps: &DepositWithIntentBumps) -> DepositShares<'_, 'info> {
+        DepositShares {
+            vault: &mut self.vault,
+            vault_token_account: &mut self.vault_token_account,
+            iou_token_mint: &self.iou_token_mint,
+            iou_token_program: &self.iou_token_program,
+            payer: self.user.key(),
+            beneficiary: self.user.key(),
+            beneficiary_iou_account: &self.user_iou_account,
+            beneficiary_position: &mut self.user_position,
+            beneficiary_position_bump: bumps.user_position,
+            rewards: RewardAccounts {
+                reward_campaign: self.reward_campaign.as_ref(),
+                reward_checkpoint: self.reward_checkpoint.as_mut(),
+                reward_checkpoint_bump: bumps.reward_checkpoint,
+            },
+        }
+    }
+}
+
+
+
+
+
+
@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::errors::VaultError;
+use crate::state::{RewardCampaign, Vault};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FundRewards<'info> {
+    #[account(
+        constraint = authority.key() == vault.authority @ VaultError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Vault's active reward campaign
+    #[account(
+        mut,
+        seeds = [b"reward_campaign", vault.key().as_ref(), reward_mint.key().as_ref()],
+        bump = reward_campaign.bump,
+        constraint = reward_campaign.key() == vault.reward_campaign @ VaultError::RewardCampaignInactive
+    )]
+    pub reward_campaign: Account<'info, RewardCampaign>,
+
+    /// Authority's reward token account (source)
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = authority,
+        associated_token::token_program = reward_token_program
+    )]
+    pub authority_reward_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Campaign's reward token account
+    #[account(
+        mut,
+        address = reward_campaign.reward_token_account
+    )]
+    pub campaign_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Reward token mint
+    #[account(address = reward_campaign.reward_mint)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    pub reward_token_program: Interface<'info, TokenInterface>,
+}
+
+
+
+
+
+
+
@@ -0,0 +1,92 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::Token2022,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::adapter::StrategyAdapter;
+use crate::errors::VaultError;
+use crate::state::{Strategy, Vault};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Harvest<'info> {
+    #[account(
+        constraint = authority.key() == vault.authority @ VaultError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump,
+        constraint = vault.nav_oracle == Pubkey::default() @ VaultError::NavOracleEnabled
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"strategy", vault.key().as_ref(), strategy.strategy_program.as_ref()],
+        bump = strategy.bump
+    )]
+    pub strategy: Account<'info, Strategy>,
+
+    /// CHECK: approved strategy program, called through the adapter interface
+    #[account(
+        executable,
+        address = strategy.strategy_program
+    )]
+    pub strategy_program: UncheckedAccount<'info>,
+
+    /// Vault's token account
+    #[account(
+        mut,
+        address = vault.vault_token_account
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Strategy's token account
+    #[account(
+        mut,
+        address = strategy.strategy_token_account
+    )]
+    pub strategy_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Deposit token mint
+    #[account(address = vault.deposit_token_mint)]
+    pub deposit_token_mint: InterfaceAccount<'info, Mint>,
+
+    /// IOU token mint, required when the vault uses the interest-bearing display mode
+    #[account(
+        mut,
+        seeds = [b"vault", vault.key().as_ref()],
+        bump = vault.iou_mint_bump
+    )]
+    pub iou_token_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub iou_token_program: Option<Program<'info, Token2022>>,
+}
+
+impl<'info> Harvest<'info> {
+    /// Adapter for the strategy program, forwarding the registered strategy-specific remaining accounts
+    pub fn strategy_adapter<'a>(&self, remaining_accounts: &'a [AccountInfo<'info>]) -> StrategyAdapter<'a, 'info> {
+        StrategyAdapter {
+            strategy_program: self.strategy_program.to_account_info(),
+            strategy: self.strategy.to_account_info(),
+            vault: self.vault.to_account_info(),
+            vault_token_account: self.vault_token_account.to_account_info(),
+            strategy_token_account: self.strategy_token_account.to_account_info(),
+            deposit_token_mint: self.deposit_token_mint.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            adapter_accounts: self.strategy.adapter_accounts.clone(),
+            remaining_accounts,
+        }
+    }
+}
+
+
+
+
+
+
@@ -1,9 +1,13 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::{
+    token_2022::Token2022,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
 
 use crate::errors::VaultError;
 use crate::state::Vault;
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct IncreaseRate<'info> {
     #[account(
@@ -15,7 +19,8 @@ pub struct IncreaseRate<'info> {
     #[account(
         mut,
         seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
-        bump = vault.bump
+        bump = vault.bump,
+        constraint = vault.nav_oracle == Pubkey::default() @ VaultError::NavOracleEnabled
     )]
     pub vault: Account<'info, Vault>,
 
@@ -23,22 +28,32 @@ pub struct IncreaseRate<'info> {
     #[account(
         mut,
         associated_token::mint = deposit_token_mint,
-        associated_token::authority = authority
+        associated_token::authority = authority,
+        associated_token::token_program = token_program
     )]
-    pub authority_token_account: Account<'info, TokenAccount>,
+    pub authority_token_account: InterfaceAccount<'info, TokenAccount>,
 
     /// Vault's token account
     #[account(
         mut,
         address = vault.vault_token_account
     )]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
 
     /// Deposit token mint
     #[account(address = vault.deposit_token_mint)]
-    pub deposit_token_mint: Account<'info, Mint>,
+    pub deposit_token_mint: InterfaceAccount<'info, Mint>,
 
-    pub token_program: Program<'info, Token>,
+    /// IOU token mint, its display rate is updated in interest-bearing mode
+    #[account(
+        mut,
+        seeds = [b"vault", vault.key().as_ref()],
+        bump = vault.iou_mint_bump
+    )]
+    pub iou_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub iou_token_program: Program<'info, Token2022>,
 }
 
 
@@ -1,18 +1,21 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount},
+    token_2022::Token2022,
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
 use crate::state::Vault;
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Initialize<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
 
-    /// The token that users will deposit
-    pub deposit_token_mint: Account<'info, Mint>,
+    /// The token that users will deposit (SPL Token or Token-2022)
+    #[account(mint::token_program = token_program)]
+    pub deposit_token_mint: InterfaceAccount<'info, Mint>,
 
     /// Vault state account (PDA)
     #[account(
@@ -25,31 +28,45 @@ pub struct Initialize<'info> {
     pub vault: Account<'info, Vault>,
 
     /// IOU/Share token mint (PDA, authority is the mint itself for self-signing)
+    /// Created as a Token-2022 mint by the handler, since its extensions depend on the initialize options
+    /// CHECK: created and initialized by the handler at this PDA address
     #[account(
-        init,
-        payer = authority,
-        mint::decimals = deposit_token_mint.decimals,
-        mint::authority = iou_token_mint,
+        mut,
         seeds = [b"vault", vault.key().as_ref()],
         bump
     )]
-    pub iou_token_mint: Account<'info, Mint>,
+    pub iou_token_mint: UncheckedAccount<'info>,
 
     /// Vault's token account to hold deposited tokens
     #[account(
         init,
         payer = authority,
         associated_token::mint = deposit_token_mint,
-        associated_token::authority = vault
+        associated_token::authority = vault,
+        associated_token::token_program = token_program
     )]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Transfer hook program attached to the IOU mint, required unless the shares are non-transferable
+    /// CHECK: only its address is recorded in the IOU mint's transfer hook extension
+    #[account(executable)]
+    pub transfer_hook_program: Option<UncheckedAccount<'info>>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub iou_token_program: Program<'info, Token2022>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
 }
 
+/// Token metadata written to the IOU mint on initialization
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct IouMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
 
 
 
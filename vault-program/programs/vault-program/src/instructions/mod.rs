@@ -1,16 +1,70 @@
 pub mod initialize;
 pub mod deposit;
+pub mod deposit_sol;
 pub mod request_withdrawal;
 pub mod claim_withdrawal;
+pub mod claim_withdrawal_sol;
 pub mod increase_rate;
 pub mod advance_epoch;
+pub mod configure_crank_bounty;
+pub mod update_iou_metadata;
+pub mod set_allowlist;
+pub mod configure_nav_oracle;
+pub mod sync_nav;
+pub mod add_strategy;
+pub mod allocate;
+pub mod harvest;
+pub mod set_idle_buffer;
+pub mod report_loss;
+pub mod set_loss_reporter;
+pub mod configure_surplus_recipient;
+pub mod sync;
+pub mod audit;
+pub mod preview;
+pub mod deposit_for;
+pub mod set_operator;
+pub mod request_withdrawal_for;
+pub mod claim_withdrawal_for;
+pub mod deposit_with_intent;
+pub mod create_reward_campaign;
+pub mod fund_rewards;
+pub mod claim_rewards;
+pub mod view_position;
+pub mod checkpoint_share_transfer;
 
 pub use initialize::*;
 pub use deposit::*;
+pub use deposit_sol::*;
 pub use request_withdrawal::*;
 pub use claim_withdrawal::*;
+pub use claim_withdrawal_sol::*;
 pub use increase_rate::*;
 pub use advance_epoch::*;
+pub use configure_crank_bounty::*;
+pub use update_iou_metadata::*;
+pub use set_allowlist::*;
+pub use configure_nav_oracle::*;
+pub use sync_nav::*;
+pub use add_strategy::*;
+pub use allocate::*;
+pub use harvest::*;
+pub use set_idle_buffer::*;
+pub use report_loss::*;
+pub use set_loss_reporter::*;
+pub use configure_surplus_recipient::*;
+pub use sync::*;
+pub use audit::*;
+pub use preview::*;
+pub use deposit_for::*;
+pub use set_operator::*;
+pub use request_withdrawal_for::*;
+pub use claim_withdrawal_for::*;
+pub use deposit_with_intent::*;
+pub use create_reward_campaign::*;
+pub use fund_rewards::*;
+pub use claim_rewards::*;
+pub use view_position::*;
+pub use checkpoint_share_transfer::*;
 
 
 
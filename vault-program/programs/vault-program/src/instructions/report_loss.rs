@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{token_2022::Token2022, token_interface::Mint};
+
+use crate::errors::VaultError;
+use crate::state::{Strategy, Vault};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReportLoss<'info> {
+    /// Vault authority or the configured loss reporter
+    #[account(
+        constraint = reporter.key() == vault.authority || reporter.key() == vault.loss_reporter @ VaultError::Unauthorized
+    )]
+    pub reporter: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump,
+        constraint = vault.nav_oracle == Pubkey::default() @ VaultError::NavOracleEnabled
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Strategy whose deployed assets lost value
+    #[account(
+        mut,
+        seeds = [b"strategy", vault.key().as_ref(), strategy.strategy_program.as_ref()],
+        bump = strategy.bump
+    )]
+    pub strategy: Account<'info, Strategy>,
+
+    /// IOU token mint, required when the vault uses the interest-bearing display mode
+    #[account(
+        mut,
+        seeds = [b"vault", vault.key().as_ref()],
+        bump = vault.iou_mint_bump
+    )]
+    pub iou_token_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    pub iou_token_program: Option<Program<'info, Token2022>>,
+}
+
+
+
+
+
+
@@ -1,8 +1,13 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::{
+    token_2022::Token2022,
+    token_interface::{Mint, TokenAccount},
+};
 
-use crate::state::{Vault, WithdrawalRequest};
+use crate::state::{RewardCampaign, RewardCheckpoint, UserPosition, Vault, WithdrawalRequest};
+use crate::utils::{RequestShares, RewardAccounts};
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct RequestWithdrawal<'info> {
     #[account(mut)]
@@ -29,9 +34,34 @@ pub struct RequestWithdrawal<'info> {
     #[account(
         mut,
         associated_token::mint = iou_token_mint,
-        associated_token::authority = user
+        associated_token::authority = user,
+        associated_token::token_program = iou_token_program
     )]
-    pub user_iou_account: Account<'info, TokenAccount>,
+    pub user_iou_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// User's position in the vault (PDA per vault and user)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    /// Vault's active reward campaign, required while the vault has one
+    #[account(address = vault.reward_campaign)]
+    pub reward_campaign: Option<Account<'info, RewardCampaign>>,
+
+    /// User's checkpoint in the active reward campaign (PDA per campaign and user)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + RewardCheckpoint::INIT_SPACE,
+        seeds = [b"reward_checkpoint", vault.reward_campaign.as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub reward_checkpoint: Option<Account<'info, RewardCheckpoint>>,
 
     /// IOU token mint
     #[account(
@@ -39,12 +69,35 @@ pub struct RequestWithdrawal<'info> {
         seeds = [b"vault", vault.key().as_ref()],
         bump = vault.iou_mint_bump
     )]
-    pub iou_token_mint: Account<'info, Mint>,
+    pub iou_token_mint: InterfaceAccount<'info, Mint>,
 
-    pub token_program: Program<'info, Token>,
+    pub iou_token_program: Program<'info, Token2022>,
     pub system_program: Program<'info, System>,
 }
 
+impl<'info> RequestWithdrawal<'info> {
+    /// Accounts turning the user's shares into the withdrawal request, burned with their signature
+    pub fn request_shares(&mut self, bumps: &RequestWithdrawalBumps) -> RequestShares<'_, 'info> {
+        RequestShares {
+            vault: &mut self.vault,
+            withdrawal_request: &mut self.withdrawal_request,
+            withdrawal_request_bump: bumps.withdrawal_request,
+            owner: self.user.key(),
+            owner_iou_account: &self.user_iou_account,
+            owner_position: &mut self.user_position,
+            owner_position_bump: bumps.user_position,
+            burn_authority: self.user.to_account_info(),
+            iou_token_mint: &self.iou_token_mint,
+            iou_token_program: &self.iou_token_program,
+            rewards: RewardAccounts {
+                reward_campaign: self.reward_campaign.as_ref(),
+                reward_checkpoint: self.reward_checkpoint.as_mut(),
+                reward_checkpoint_bump: bumps.reward_checkpoint,
+            },
+        }
+    }
+}
+
 
 
 
@@ -0,0 +1,125 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::Token2022,
+    token_interface::{Mint, TokenAccount},
+};
+
+use crate::errors::VaultError;
+use crate::state::{Operator, RewardCampaign, RewardCheckpoint, UserPosition, Vault, WithdrawalRequest};
+use crate::utils::{RequestShares, RewardAccounts};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RequestWithdrawalFor<'info> {
+    /// Approved operator, pays for the withdrawal request account if it does not exist yet
+    #[account(mut)]
+    pub operator: Signer<'info>,
+
+    /// CHECK: only used to derive the owner's accounts, the operator approval ties it to the operator
+    pub owner: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Owner's approval of the operator
+    #[account(
+        seeds = [b"operator", vault.key().as_ref(), owner.key().as_ref(), operator.key().as_ref()],
+        bump = operator_approval.bump,
+        constraint = operator_approval.approved @ VaultError::OperatorNotApproved
+    )]
+    pub operator_approval: Account<'info, Operator>,
+
+    /// Owner's withdrawal request account
+    #[account(
+        init_if_needed,
+        payer = operator,
+        space = 8 + WithdrawalRequest::INIT_SPACE,
+        seeds = [b"withdrawal", vault.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+
+    /// Owner's IOU token account, burned from through the owner's delegation to the vault delegate
+    #[account(
+        mut,
+        associated_token::mint = iou_token_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = iou_token_program
+    )]
+    pub owner_iou_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: PDA users delegate their allowances to, it signs nothing but delegated burns and transfers
+    #[account(
+        seeds = [b"delegate", vault.key().as_ref()],
+        bump
+    )]
+    pub delegate: UncheckedAccount<'info>,
+
+    /// Owner's position in the vault (PDA per vault and user)
+    #[account(
+        init_if_needed,
+        payer = operator,
+        space = 8 + UserPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub owner_position: Account<'info, UserPosition>,
+
+    /// Vault's active reward campaign, required while the vault has one
+    #[account(address = vault.reward_campaign)]
+    pub reward_campaign: Option<Account<'info, RewardCampaign>>,
+
+    /// Owner's checkpoint in the active reward campaign (PDA per campaign and user)
+    #[account(
+        init_if_needed,
+        payer = operator,
+        space = 8 + RewardCheckpoint::INIT_SPACE,
+        seeds = [b"reward_checkpoint", vault.reward_campaign.as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub reward_checkpoint: Option<Account<'info, RewardCheckpoint>>,
+
+    /// IOU token mint
+    #[account(
+        mut,
+        seeds = [b"vault", vault.key().as_ref()],
+        bump = vault.iou_mint_bump
+    )]
+    pub iou_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub iou_token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RequestWithdrawalFor<'info> {
+    /// Accounts turning the owner's shares into the withdrawal request, burned through their delegation to the delegate PDA
+    pub fn request_shares(&mut self, bumps: &RequestWithdrawalForBumps) -> RequestShares<'_, 'info> {
+        RequestShares {
+            vault: &mut self.vault,
+            withdrawal_request: &mut self.withdrawal_request,
+            withdrawal_request_bump: bumps.withdrawal_request,
+            owner: self.owner.key(),
+            owner_iou_account: &self.owner_iou_account,
+            owner_position: &mut self.owner_position,
+            owner_position_bump: bumps.owner_position,
+            burn_authority: self.delegate.to_account_info(),
+            iou_token_mint: &self.iou_token_mint,
+            iou_token_program: &self.iou_token_program,
+            rewards: RewardAccounts {
+                reward_campaign: self.reward_campaign.as_ref(),
+                reward_checkpoint: self.reward_checkpoint.as_mut(),
+                reward_checkpoint_bump: bumps.reward_checkpoint,
+            },
+        }
+    }
+}
+
+
+
+
+
+
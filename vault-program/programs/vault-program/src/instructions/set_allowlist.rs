@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::VaultError;
+use crate::state::{AllowlistEntry, Vault};
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct SetAllowlist<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == vault.authority @ VaultError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Allowlist entry for the wallet (PDA per vault and wallet), read by the share transfer hook
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + AllowlistEntry::INIT_SPACE,
+        seeds = [b"allowlist", vault.key().as_ref(), wallet.as_ref()],
+        bump
+    )]
+    pub allowlist_entry: Account<'info, AllowlistEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+
+
+
+
+
@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Operator, Vault};
+
+#[derive(Accounts)]
+#[instruction(operator: Pubkey)]
+pub struct SetOperator<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Operator approval (PDA per vault, owner and operator)
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + Operator::INIT_SPACE,
+        seeds = [b"operator", vault.key().as_ref(), owner.key().as_ref(), operator.as_ref()],
+        bump
+    )]
+    pub operator_approval: Account<'info, Operator>,
+
+    pub system_program: Program<'info, System>,
+}
+
+
+
+
+
+
+
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::Token2022,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::errors::VaultError;
+use crate::state::Vault;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SyncBalance<'info> {
+    #[account(
+        constraint = authority.key() == vault.authority @ VaultError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump,
+        constraint = vault.nav_oracle == Pubkey::default() @ VaultError::NavOracleEnabled
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Vault's token account
+    #[account(
+        mut,
+        address = vault.vault_token_account
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Deposit token mint
+    #[account(address = vault.deposit_token_mint)]
+    pub deposit_token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Configured surplus recipient, required when the vault does not fold the surplus into the rate
+    #[account(
+        mut,
+        address = vault.surplus_recipient
+    )]
+    pub surplus_recipient: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// IOU token mint, required when the vault uses the interest-bearing display mode
+    #[account(
+        mut,
+        seeds = [b"vault", vault.key().as_ref()],
+        bump = vault.iou_mint_bump
+    )]
+    pub iou_token_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub iou_token_program: Option<Program<'info, Token2022>>,
+}
+
+
+
+
+
+
@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{token_2022::Token2022, token_interface::Mint};
+
+use crate::errors::VaultError;
+use crate::state::Vault;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SyncNav<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump,
+        constraint = vault.nav_oracle != Pubkey::default() @ VaultError::NavOracleNotConfigured
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// CHECK: address checked against the configured oracle, owner, layout and feed checked by the handler
+    #[account(address = vault.nav_oracle)]
+    pub nav_oracle: UncheckedAccount<'info>,
+
+    /// IOU token mint, required when the vault uses the interest-bearing display mode
+    #[account(
+        mut,
+        seeds = [b"vault", vault.key().as_ref()],
+        bump = vault.iou_mint_bump
+    )]
+    pub iou_token_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    pub iou_token_program: Option<Program<'info, Token2022>>,
+}
+
+
+
+
+
+
@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::Token2022,
+    token_interface::{spl_token_metadata_interface::state::Field, Mint},
+};
+
+use crate::constants::{MAX_METADATA_NAME_LEN, MAX_METADATA_SYMBOL_LEN, MAX_METADATA_URI_LEN};
+use crate::errors::VaultError;
+use crate::state::Vault;
+
+#[derive(Accounts)]
+pub struct UpdateIouMetadata<'info> {
+    /// Vault authority, pays for any extra rent the new metadata needs
+    #[account(
+        mut,
+        constraint = authority.key() == vault.authority @ VaultError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// IOU token mint, holds the token metadata
+    #[account(
+        mut,
+        seeds = [b"vault", vault.key().as_ref()],
+        bump = vault.iou_mint_bump
+    )]
+    pub iou_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub iou_token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+/// IOU token metadata field that the authority can update
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MetadataField {
+    Name,
+    Symbol,
+    Uri,
+}
+
+impl MetadataField {
+    /// Maximum length accepted for this field
+    pub fn max_len(&self) -> usize {
+        match self {
+            MetadataField::Name => MAX_METADATA_NAME_LEN,
+            MetadataField::Symbol => MAX_METADATA_SYMBOL_LEN,
+            MetadataField::Uri => MAX_METADATA_URI_LEN,
+        }
+    }
+}
+
+impl From<MetadataField> for Field {
+    fn from(field: MetadataField) -> Self {
+        match field {
+            MetadataField::Name => Field::Name,
+            MetadataField::Symbol => Field::Symbol,
+            MetadataField::Uri => Field::Uri,
+        }
+    }
+}
+
+
+
+
+
+
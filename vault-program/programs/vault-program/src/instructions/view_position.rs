@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{UserPosition, Vault};
+
+#[derive(Accounts)]
+pub struct ViewPosition<'info> {
+    #[account(
+        seeds = [b"vault", vault.authority.as_ref(), vault.deposit_token_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Position to value (PDA per vault and user)
+    #[account(
+        seeds = [b"position", vault.key().as_ref(), user_position.user.as_ref()],
+        bump = user_position.bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+}
+
+
+
+
+
+
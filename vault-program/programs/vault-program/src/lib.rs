@@ -1,15 +1,29 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Burn, MintTo, Transfer};
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::system_program::{self, CreateAccount, Transfer};
+use anchor_spl::token_2022::spl_token_2022::{extension::ExtensionType, state::Mint as MintState};
+use anchor_spl::token_interface::{
+    self,
+    spl_pod::optional_keys::OptionalNonZeroPubkey,
+    spl_token_metadata_interface::state::TokenMetadata,
+    InitializeMint2, InterestBearingMintInitialize, MetadataPointerInitialize,
+    CloseAccount, NonTransferableMintInitialize, SyncNative, TokenMetadataInitialize,
+    TokenMetadataUpdateField, TransferChecked, TransferHookInitialize,
+};
 
+pub mod adapter;
 pub mod constants;
 pub mod errors;
+pub mod events;
 pub mod instructions;
 pub mod state;
 pub mod utils;
 
 use constants::*;
 use errors::*;
+use events::*;
 use instructions::*;
+use state::{BountyKind, DepositIntent, EpochMode, PositionYield, RewardCampaign, ShareTransferMode};
 use utils::*;
 
 declare_id!("D3ioGqnnBE4CkW7TN3Cb7Va2BG1sb4VE5vk5KKYoogwx");
@@ -20,8 +34,36 @@ pub mod vault_program {
 
     /// Initialize the vault with a deposit token
     /// Creates the vault state and the IOU (share) token mint
-    pub fn initialize(ctx: Context<Initialize>, epoch_duration: i64) -> Result<()> {
+    /// `epoch_duration` is expressed in the units of `epoch_mode`
+    /// `metadata` is written as on-chain token metadata of the IOU mint
+    /// `interest_bearing_iou` makes wallets display IOU balances in approximate underlying tokens
+    /// `share_transfer_mode` can lock IOU shares to their holder or gate transfers with the vault allowlist
+    /// Transferable shares also route transfers through the share transfer hook, which checkpoints reward balances
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        epoch_duration: i64,
+        epoch_mode: EpochMode,
+        metadata: IouMetadata,
+        interest_bearing_iou: bool,
+        share_transfer_mode: ShareTransferMode,
+    ) -> Result<()> {
+        let IouMetadata { name, symbol, uri } = metadata;
+        require!(epoch_duration > 0, VaultError::InvalidEpochDuration);
+        require!(name.len() <= MAX_METADATA_NAME_LEN, VaultError::MetadataTooLong);
+        require!(symbol.len() <= MAX_METADATA_SYMBOL_LEN, VaultError::MetadataTooLong);
+        require!(uri.len() <= MAX_METADATA_URI_LEN, VaultError::MetadataTooLong);
+        let transfer_hook_program_id = match share_transfer_mode {
+            ShareTransferMode::Transferable | ShareTransferMode::TransferHook => Some(
+                ctx.accounts.transfer_hook_program
+                    .as_ref()
+                    .ok_or(VaultError::MissingTransferHookProgram)?
+                    .key(),
+            ),
+            ShareTransferMode::NonTransferable => None,
+        };
+
         let vault = &mut ctx.accounts.vault;
+        let clock = Clock::get()?;
         
         vault.authority = ctx.accounts.authority.key();
         vault.deposit_token_mint = ctx.accounts.deposit_token_mint.key();
@@ -31,137 +73,275 @@ pub mod vault_program {
         vault.total_shares = 0;
         vault.rate = INITIAL_RATE;
         vault.current_epoch = 0;
+        vault.epoch_mode = epoch_mode;
         vault.epoch_duration = epoch_duration;
-        vault.last_epoch_timestamp = Clock::get()?.unix_timestamp;
+        vault.last_epoch_timestamp = clock.unix_timestamp;
+        vault.last_epoch_slot = clock.slot;
+        vault.last_solana_epoch = clock.epoch;
+        vault.interest_bearing_iou = interest_bearing_iou;
+        vault.share_transfer_mode = share_transfer_mode;
+        vault.nav_oracle = Pubkey::default();
+        vault.nav_max_staleness = 0;
+        vault.nav_max_confidence_bps = 0;
+        vault.nav_synced_slot = 0;
+        vault.deployed_assets = 0;
+        vault.pending_withdrawals = 0;
+        vault.idle_buffer_bps = 0;
+        vault.loss_reporter = ctx.accounts.authority.key();
+        vault.withdrawal_loss_index = RATE_PRECISION;
+        vault.surplus_recipient = Pubkey::default();
+        vault.reward_campaign = Pubkey::default();
         vault.bump = ctx.bumps.vault;
         vault.iou_mint_bump = ctx.bumps.iou_token_mint;
 
-        msg!("Vault initialized with epoch duration: {} seconds", epoch_duration);
+        let vault_key = vault.key();
+        let seeds = &[
+            b"vault".as_ref(),
+            vault_key.as_ref(),
+            &[vault.iou_mint_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let iou_mint_info = ctx.accounts.iou_token_mint.to_account_info();
+        let iou_token_program_info = ctx.accounts.iou_token_program.to_account_info();
+
+        // Create the IOU mint account with room for its extensions and rent for the metadata written below
+        let mut extensions = vec![ExtensionType::MetadataPointer];
+        if interest_bearing_iou {
+            extensions.push(ExtensionType::InterestBearingConfig);
+        }
+        match share_transfer_mode {
+            ShareTransferMode::NonTransferable => extensions.push(ExtensionType::NonTransferable),
+            ShareTransferMode::Transferable | ShareTransferMode::TransferHook => {
+                extensions.push(ExtensionType::TransferHook)
+            }
+        }
+        let mint_len = ExtensionType::try_calculate_account_len::<MintState>(&extensions)?;
+
+        let token_metadata = TokenMetadata {
+            update_authority: OptionalNonZeroPubkey::try_from(Some(iou_mint_info.key()))?,
+            mint: iou_mint_info.key(),
+            name: name.clone(),
+            symbol: symbol.clone(),
+            uri: uri.clone(),
+            additional_metadata: vec![],
+        };
+        let lamports = Rent::get()?.minimum_balance(mint_len + token_metadata.tlv_size_of()?);
+
+        let create_ctx = CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            CreateAccount {
+                from: ctx.accounts.authority.to_account_info(),
+                to: iou_mint_info.clone(),
+            },
+            signer_seeds,
+        );
+        system_program::create_account(create_ctx, lamports, mint_len as u64, &iou_token_program_info.key())?;
+
+        // Extensions must be initialized before the mint itself
+        let pointer_ctx = CpiContext::new(
+            iou_token_program_info.clone(),
+            MetadataPointerInitialize {
+                token_program_id: iou_token_program_info.clone(),
+                mint: iou_mint_info.clone(),
+            },
+        );
+        token_interface::metadata_pointer_initialize(pointer_ctx, Some(iou_mint_info.key()), Some(iou_mint_info.key()))?;
+
+        if interest_bearing_iou {
+            // Starts at 0%, updated from increase_rate and epoch changes
+            let interest_ctx = CpiContext::new(
+                iou_token_program_info.clone(),
+                InterestBearingMintInitialize {
+                    token_program_id: iou_token_program_info.clone(),
+                    mint: iou_mint_info.clone(),
+                },
+            );
+            token_interface::interest_bearing_mint_initialize(interest_ctx, Some(iou_mint_info.key()), 0)?;
+        }
+
+        match share_transfer_mode {
+            ShareTransferMode::NonTransferable => {
+                let non_transferable_ctx = CpiContext::new(
+                    iou_token_program_info.clone(),
+                    NonTransferableMintInitialize {
+                        token_program_id: iou_token_program_info.clone(),
+                        mint: iou_mint_info.clone(),
+                    },
+                );
+                token_interface::non_transferable_mint_initialize(non_transferable_ctx)?;
+            }
+            ShareTransferMode::Transferable | ShareTransferMode::TransferHook => {
+                // The mint PDA stays the hook authority so the hook program cannot be swapped out
+                let hook_ctx = CpiContext::new(
+                    iou_token_program_info.clone(),
+                    TransferHookInitialize {
+                        token_program_id: iou_token_program_info.clone(),
+                        mint: iou_mint_info.clone(),
+                    },
+                );
+                token_interface::transfer_hook_initialize(hook_ctx, Some(iou_mint_info.key()), transfer_hook_program_id)?;
+            }
+        }
+
+        let mint_ctx = CpiContext::new(
+            iou_token_program_info.clone(),
+            InitializeMint2 {
+                mint: iou_mint_info.clone(),
+            },
+        );
+        token_interface::initialize_mint2(mint_ctx, ctx.accounts.deposit_token_mint.decimals, &iou_mint_info.key(), None)?;
+
+        // Write the IOU token metadata into the mint, the mint PDA is its update authority
+        let metadata_ctx = CpiContext::new_with_signer(
+            iou_token_program_info.clone(),
+            TokenMetadataInitialize {
+                program_id: iou_token_program_info.clone(),
+                metadata: iou_mint_info.clone(),
+                update_authority: iou_mint_info.clone(),
+                mint_authority: iou_mint_info.clone(),
+                mint: iou_mint_info.clone(),
+            },
+            signer_seeds,
+        );
+        token_interface::token_metadata_initialize(metadata_ctx, name.clone(), symbol.clone(), uri)?;
+
+        emit_cpi!(VaultInitialized {
+            vault: vault.key(),
+            authority: vault.authority,
+            deposit_token_mint: vault.deposit_token_mint,
+            iou_token_mint: vault.iou_token_mint,
+            vault_token_account: vault.vault_token_account,
+            epoch_mode,
+            epoch_duration,
+            rate: vault.rate,
+            interest_bearing_iou,
+            share_transfer_mode,
+        });
+
+        msg!("Vault initialized with epoch duration: {} ({:?})", epoch_duration, epoch_mode);
         msg!("Deposit token: {}", vault.deposit_token_mint);
-        msg!("IOU token: {}", vault.iou_token_mint);
+        msg!("IOU token: {} ({} / {})", vault.iou_token_mint, name, symbol);
+        if interest_bearing_iou {
+            msg!("IOU token uses the interest-bearing display mode");
+        }
+        msg!("IOU share transfers: {:?}", share_transfer_mode);
 
         Ok(())
     }
 
     /// Deposit tokens into the vault and receive IOU shares
-    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+    /// Fails if fewer than `min_shares_out` shares would be minted or if `deadline` has passed
+    pub fn deposit(ctx: Context<Deposit>, amount: u64, min_shares_out: u64, deadline: Option<i64>) -> Result<()> {
         require!(amount > 0, VaultError::InvalidAmount);
+        let clock = Clock::get()?;
+        check_deadline(deadline, &clock)?;
 
-        let vault = &mut ctx.accounts.vault;
-
-        // Calculate shares to mint based on current rate
-        let shares_to_mint = calculate_shares_from_amount(amount, vault.rate)?;
-        require!(shares_to_mint > 0, VaultError::InsufficientShares);
+        let balance_before = ctx.accounts.vault_token_account.amount;
 
         // Transfer tokens from user to vault
         let transfer_ctx = CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
-            Transfer {
+            TransferChecked {
                 from: ctx.accounts.user_token_account.to_account_info(),
+                mint: ctx.accounts.deposit_token_mint.to_account_info(),
                 to: ctx.accounts.vault_token_account.to_account_info(),
                 authority: ctx.accounts.user.to_account_info(),
             },
         );
-        token::transfer(transfer_ctx, amount)?;
-
-        // Mint IOU shares to user
-        let vault_key = vault.key();
-        let seeds = &[
-            b"vault".as_ref(),
-            vault_key.as_ref(),
-            &[vault.iou_mint_bump],
-        ];
-        let signer_seeds = &[&seeds[..]];
-
-        let mint_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            MintTo {
-                mint: ctx.accounts.iou_token_mint.to_account_info(),
-                to: ctx.accounts.user_iou_account.to_account_info(),
-                authority: ctx.accounts.iou_token_mint.to_account_info(),
-            },
-            signer_seeds,
-        );
-        token::mint_to(mint_ctx, shares_to_mint)?;
-
-        // Update vault state
-        vault.total_deposits = vault.total_deposits.checked_add(amount).ok_or(VaultError::MathOverflow)?;
-        vault.total_shares = vault.total_shares.checked_add(shares_to_mint).ok_or(VaultError::MathOverflow)?;
+        token_interface::transfer_checked(transfer_ctx, amount, ctx.accounts.deposit_token_mint.decimals)?;
 
-        msg!("Deposited {} tokens, minted {} shares", amount, shares_to_mint);
-        msg!("Current rate: {}", vault.rate);
+        let deposited = ctx.accounts
+            .deposit_shares(&ctx.bumps)
+            .credit(amount, balance_before, min_shares_out, &clock)?;
+        emit_cpi!(deposited);
 
         Ok(())
     }
 
-    /// Request a withdrawal - locks the shares for the next epoch
-    pub fn request_withdrawal(ctx: Context<RequestWithdrawal>, shares_amount: u64) -> Result<()> {
-        require!(shares_amount > 0, VaultError::InvalidAmount);
+    /// Deposit native SOL into a wSOL vault and receive IOU shares
+    /// The lamports are wrapped straight into the vault's wSOL account, the accounting is the same as `deposit`
+    pub fn deposit_sol(ctx: Context<DepositSol>, amount: u64, min_shares_out: u64, deadline: Option<i64>) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        let clock = Clock::get()?;
+        check_deadline(deadline, &clock)?;
 
-        let vault = &mut ctx.accounts.vault;
-        let withdrawal_request = &mut ctx.accounts.withdrawal_request;
-        
-        // Check if there's already a pending withdrawal request
-        require!(
-            withdrawal_request.shares_amount == 0 || withdrawal_request.claimed,
-            VaultError::PendingWithdrawalExists
-        );
+        let balance_before = ctx.accounts.vault_token_account.amount;
 
-        // Calculate the epoch when withdrawal can be claimed
-        let current_epoch = calculate_current_epoch(vault)?;
-        let claimable_epoch = current_epoch.checked_add(1).ok_or(VaultError::MathOverflow)?;
+        // Send lamports to the vault's wSOL account, then sync its token balance with its lamports
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.user.to_account_info(),
+                to: ctx.accounts.vault_token_account.to_account_info(),
+            },
+        );
+        system_program::transfer(transfer_ctx, amount)?;
 
-        // Burn the IOU shares from user
-        let burn_ctx = CpiContext::new(
+        let sync_ctx = CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
-            Burn {
-                mint: ctx.accounts.iou_token_mint.to_account_info(),
-                from: ctx.accounts.user_iou_account.to_account_info(),
-                authority: ctx.accounts.user.to_account_info(),
+            SyncNative {
+                account: ctx.accounts.vault_token_account.to_account_info(),
             },
         );
-        token::burn(burn_ctx, shares_amount)?;
+        token_interface::sync_native(sync_ctx)?;
 
-        // Calculate tokens to receive based on current rate
-        let tokens_to_receive = calculate_amount_from_shares(shares_amount, vault.rate)?;
+        let deposited = ctx.accounts
+            .deposit_shares(&ctx.bumps)
+            .credit(amount, balance_before, min_shares_out, &clock)?;
+        emit_cpi!(deposited);
+
+        Ok(())
+    }
 
-        // Update total_shares to reflect burned shares
-        vault.total_shares = vault.total_shares.checked_sub(shares_amount).ok_or(VaultError::MathOverflow)?;
+    /// Request a withdrawal - locks the shares for the next epoch
+    /// Fails if fewer than `min_tokens_out` tokens would be locked or if `deadline` has passed
+    /// The claim pays `receiver` when given, otherwise the user
+    pub fn request_withdrawal(
+        ctx: Context<RequestWithdrawal>,
+        shares_amount: u64,
+        min_tokens_out: u64,
+        deadline: Option<i64>,
+        receiver: Option<Pubkey>,
+    ) -> Result<()> {
+        require!(shares_amount > 0, VaultError::InvalidAmount);
 
-        // Create or update withdrawal request
-        withdrawal_request.user = ctx.accounts.user.key();
-        withdrawal_request.vault = vault.key();
-        withdrawal_request.shares_amount = shares_amount;
-        withdrawal_request.tokens_to_receive = tokens_to_receive;
-        withdrawal_request.request_epoch = current_epoch;
-        withdrawal_request.claimable_epoch = claimable_epoch;
-        withdrawal_request.claimed = false;
-        withdrawal_request.bump = ctx.bumps.withdrawal_request;
+        let clock = Clock::get()?;
+        check_deadline(deadline, &clock)?;
 
-        msg!("Withdrawal requested: {} shares = {} tokens", shares_amount, tokens_to_receive);
-        msg!("Current epoch: {}, Claimable at epoch: {}", current_epoch, claimable_epoch);
+        // The user signs the burn of their own shares
+        let receiver = receiver.unwrap_or(ctx.accounts.user.key());
+        let requested = ctx.accounts
+            .request_shares(&ctx.bumps)
+            .request(shares_amount, min_tokens_out, receiver, &[], &clock)?;
+        emit_cpi!(requested);
 
         Ok(())
     }
 
     /// Claim a pending withdrawal after the epoch has passed
-    pub fn claim_withdrawal(ctx: Context<ClaimWithdrawal>) -> Result<()> {
-        let vault = &mut ctx.accounts.vault;
-        let withdrawal_request = &mut ctx.accounts.withdrawal_request;
+    /// Returns the amount the user nets after any Token-2022 transfer fee
+    pub fn claim_withdrawal<'info>(ctx: Context<'_, '_, '_, 'info, ClaimWithdrawal<'info>>) -> Result<u64> {
+        let clock = Clock::get()?;
+        let claim = ctx.accounts.claim_payout(ctx.remaining_accounts).pay(&clock)?;
 
-        // Verify the request hasn't been claimed
-        require!(!withdrawal_request.claimed, VaultError::AlreadyClaimed);
+        if let Some(haircut) = claim.haircut {
+            emit_cpi!(haircut);
+        }
+        emit_cpi!(claim.claimed);
 
-        // Verify we're in a valid epoch
-        let current_epoch = calculate_current_epoch(vault)?;
-        require!(
-            current_epoch >= withdrawal_request.claimable_epoch,
-            VaultError::EpochNotReached
-        );
+        Ok(claim.tokens_net)
+    }
 
-        let tokens_to_transfer = withdrawal_request.tokens_to_receive;
+    /// Claim a withdrawal from a wSOL vault as native SOL
+    /// The tokens are moved to a temporary wSOL account which is closed to the receiver, unwrapping them
+    /// Returns the lamports the receiver nets, not counting the temporary account rent
+    pub fn claim_withdrawal_sol<'info>(ctx: Context<'_, '_, '_, 'info, ClaimWithdrawalSol<'info>>) -> Result<u64> {
+        let clock = Clock::get()?;
+        let claim = ctx.accounts.claim_payout(ctx.remaining_accounts).pay(&clock)?;
 
-        // Transfer tokens from vault to user
+        // Closing the wSOL account sends both the claimed lamports and its rent to the receiver
+        let vault = &ctx.accounts.vault;
         let authority_key = vault.authority;
         let seeds = &[
             b"vault".as_ref(),
@@ -171,26 +351,25 @@ pub mod vault_program {
         ];
         let signer_seeds = &[&seeds[..]];
 
-        let transfer_ctx = CpiContext::new_with_signer(
+        let close_ctx = CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.vault_token_account.to_account_info(),
-                to: ctx.accounts.user_token_account.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.unwrap_token_account.to_account_info(),
+                destination: ctx.accounts.receiver.to_account_info(),
                 authority: vault.to_account_info(),
             },
             signer_seeds,
         );
-        token::transfer(transfer_ctx, tokens_to_transfer)?;
-
-        // Update vault state
-        vault.total_deposits = vault.total_deposits.checked_sub(tokens_to_transfer).ok_or(VaultError::MathOverflow)?;
+        token_interface::close_account(close_ctx)?;
 
-        // Mark as claimed
-        withdrawal_request.claimed = true;
+        if let Some(haircut) = claim.haircut {
+            emit_cpi!(haircut);
+        }
+        emit_cpi!(claim.claimed);
 
-        msg!("Claimed {} tokens", tokens_to_transfer);
+        msg!("Unwrapped {} lamports to the receiver", claim.tokens_net);
 
-        Ok(())
+        Ok(claim.tokens_net)
     }
 
     /// Increase the rate to simulate vault rewards/yield (admin only)
@@ -198,56 +377,176 @@ pub mod vault_program {
         require!(additional_tokens > 0, VaultError::InvalidAmount);
 
         let vault = &mut ctx.accounts.vault;
+        let balance_before = ctx.accounts.vault_token_account.amount;
+        let rate_before = vault.rate;
 
         // Transfer additional tokens to vault (simulating rewards)
         let transfer_ctx = CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
-            Transfer {
+            TransferChecked {
                 from: ctx.accounts.authority_token_account.to_account_info(),
+                mint: ctx.accounts.deposit_token_mint.to_account_info(),
                 to: ctx.accounts.vault_token_account.to_account_info(),
                 authority: ctx.accounts.authority.to_account_info(),
             },
         );
-        token::transfer(transfer_ctx, additional_tokens)?;
+        token_interface::transfer_checked(transfer_ctx, additional_tokens, ctx.accounts.deposit_token_mint.decimals)?;
+
+        // Only credit what the vault actually received
+        ctx.accounts.vault_token_account.reload()?;
+        let tokens_received = ctx.accounts.vault_token_account.amount
+            .checked_sub(balance_before)
+            .ok_or(VaultError::MathOverflow)?;
 
         // Update total deposits
-        vault.total_deposits = vault.total_deposits.checked_add(additional_tokens).ok_or(VaultError::MathOverflow)?;
+        vault.total_deposits = vault.total_deposits.checked_add(tokens_received).ok_or(VaultError::MathOverflow)?;
 
-        // Recalculate rate
-        if vault.total_shares > 0 {
-            vault.rate = vault.total_deposits
-                .checked_mul(RATE_PRECISION)
-                .ok_or(VaultError::MathOverflow)?
-                .checked_div(vault.total_shares)
-                .ok_or(VaultError::MathOverflow)?;
+        // Recalculate rate, virtual shares and assets keep it defined while no shares are outstanding
+        vault.rate = calculate_rate(vault)?;
+
+        if vault.interest_bearing_iou {
+            sync_iou_display_rate(
+                &vault.key(),
+                vault,
+                &ctx.accounts.iou_token_mint.to_account_info(),
+                &ctx.accounts.iou_token_program.to_account_info(),
+                Clock::get()?.unix_timestamp,
+            )?;
         }
 
-        msg!("Rate increased! Added {} tokens", additional_tokens);
+        emit_cpi!(RateIncreased {
+            vault: vault.key(),
+            additional_tokens,
+            tokens_received,
+            rate_before,
+            rate_after: vault.rate,
+            total_deposits: vault.total_deposits,
+            total_shares: vault.total_shares,
+        });
+
+        msg!("Rate increased! Added {} tokens ({} received after transfer fee)", additional_tokens, tokens_received);
         msg!("New rate: {} (1 share = {} tokens)", vault.rate, vault.rate as f64 / RATE_PRECISION as f64);
         msg!("Total deposits: {}, Total shares: {}", vault.total_deposits, vault.total_shares);
 
         Ok(())
     }
 
-    /// Advance to the next epoch (time-based)
+    /// Advance to the next epoch once its duration has elapsed in the vault's epoch mode
     pub fn advance_epoch(ctx: Context<AdvanceEpoch>) -> Result<()> {
         let vault = &mut ctx.accounts.vault;
         let clock = Clock::get()?;
-        
-        let time_since_last_epoch = clock.unix_timestamp
-            .checked_sub(vault.last_epoch_timestamp)
-            .ok_or(VaultError::MathOverflow)?;
 
-        require!(
-            time_since_last_epoch >= vault.epoch_duration,
-            VaultError::EpochNotReached
-        );
+        let elapsed_epochs = calculate_elapsed_epochs(vault, &clock)?;
+        require!(elapsed_epochs >= 1, VaultError::EpochNotReached);
+        let previous_epoch = vault.current_epoch;
 
-        vault.current_epoch = vault.current_epoch.checked_add(1).ok_or(VaultError::MathOverflow)?;
+        match vault.epoch_mode {
+            EpochMode::UnixSeconds => {
+                vault.current_epoch = vault.current_epoch.checked_add(1).ok_or(VaultError::MathOverflow)?;
+            }
+            EpochMode::Slots | EpochMode::SolanaEpoch => {
+                // Catch up on every elapsed epoch and keep the next boundary aligned
+                let elapsed_units = elapsed_epochs
+                    .checked_mul(vault.epoch_duration as u64)
+                    .ok_or(VaultError::MathOverflow)?;
+
+                vault.current_epoch = vault.current_epoch.checked_add(elapsed_epochs).ok_or(VaultError::MathOverflow)?;
+                if vault.epoch_mode == EpochMode::Slots {
+                    vault.last_epoch_slot = vault.last_epoch_slot.checked_add(elapsed_units).ok_or(VaultError::MathOverflow)?;
+                } else {
+                    vault.last_solana_epoch = vault.last_solana_epoch.checked_add(elapsed_units).ok_or(VaultError::MathOverflow)?;
+                }
+            }
+        }
         vault.last_epoch_timestamp = clock.unix_timestamp;
 
+        if vault.interest_bearing_iou {
+            let iou_token_mint = ctx.accounts.iou_token_mint.as_ref().ok_or(VaultError::MissingIouMintAccount)?;
+            let iou_token_program = ctx.accounts.iou_token_program.as_ref().ok_or(VaultError::MissingIouMintAccount)?;
+            sync_iou_display_rate(
+                &vault.key(),
+                vault,
+                &iou_token_mint.to_account_info(),
+                &iou_token_program.to_account_info(),
+                clock.unix_timestamp,
+            )?;
+        }
+
         msg!("Advanced to epoch {}", vault.current_epoch);
 
+        // Pay the crank bounty, at most once per vault epoch
+        let mut bounty_paid = 0;
+        if let Some(crank_bounty) = ctx.accounts.crank_bounty.as_mut() {
+            let cranker = ctx.accounts.cranker.as_ref().ok_or(VaultError::MissingBountyAccount)?;
+
+            if crank_bounty.max_per_epoch > 0 && crank_bounty.last_paid_epoch < vault.current_epoch {
+                let bounty = match crank_bounty.kind {
+                    BountyKind::Lamports => {
+                        // Only lamports above the rent-exempt minimum can be paid out
+                        let bounty_info = crank_bounty.to_account_info();
+                        let rent_exempt_minimum = Rent::get()?.minimum_balance(bounty_info.data_len());
+                        let available = bounty_info.lamports().saturating_sub(rent_exempt_minimum);
+                        let bounty = crank_bounty.max_per_epoch.min(available);
+
+                        // The bounty account is owned by this program, so its lamports can be debited directly
+                        let cranker_info = cranker.to_account_info();
+                        let bounty_lamports = bounty_info.lamports().checked_sub(bounty).ok_or(VaultError::MathOverflow)?;
+                        let cranker_lamports = cranker_info.lamports().checked_add(bounty).ok_or(VaultError::MathOverflow)?;
+                        **bounty_info.try_borrow_mut_lamports()? = bounty_lamports;
+                        **cranker_info.try_borrow_mut_lamports()? = cranker_lamports;
+
+                        bounty
+                    }
+                    BountyKind::DepositToken => {
+                        let bounty_token_account = ctx.accounts.bounty_token_account.as_ref().ok_or(VaultError::MissingBountyAccount)?;
+                        let cranker_token_account = ctx.accounts.cranker_token_account.as_ref().ok_or(VaultError::MissingBountyAccount)?;
+                        let deposit_token_mint = ctx.accounts.deposit_token_mint.as_ref().ok_or(VaultError::MissingBountyAccount)?;
+                        let token_program = ctx.accounts.token_program.as_ref().ok_or(VaultError::MissingBountyAccount)?;
+                        require_keys_eq!(bounty_token_account.owner, crank_bounty.key(), VaultError::MissingBountyAccount);
+
+                        let bounty = crank_bounty.max_per_epoch.min(bounty_token_account.amount);
+
+                        let vault_key = vault.key();
+                        let seeds = &[
+                            b"crank_bounty".as_ref(),
+                            vault_key.as_ref(),
+                            &[crank_bounty.bump],
+                        ];
+                        let signer_seeds = &[&seeds[..]];
+
+                        let transfer_ctx = CpiContext::new_with_signer(
+                            token_program.to_account_info(),
+                            TransferChecked {
+                                from: bounty_token_account.to_account_info(),
+                                mint: deposit_token_mint.to_account_info(),
+                                to: cranker_token_account.to_account_info(),
+                                authority: crank_bounty.to_account_info(),
+                            },
+                            signer_seeds,
+                        );
+                        token_interface::transfer_checked(transfer_ctx, bounty, deposit_token_mint.decimals)?;
+
+                        bounty
+                    }
+                };
+
+                crank_bounty.last_paid_epoch = vault.current_epoch;
+                crank_bounty.total_paid = crank_bounty.total_paid.checked_add(bounty).ok_or(VaultError::MathOverflow)?;
+                bounty_paid = bounty;
+
+                msg!("Crank bounty of {} ({:?}) paid to {}", bounty, crank_bounty.kind, cranker.key());
+            }
+        }
+
+        emit_cpi!(EpochAdvanced {
+            vault: vault.key(),
+            previous_epoch,
+            current_epoch: vault.current_epoch,
+            forced: false,
+            bounty_paid,
+            timestamp: clock.unix_timestamp,
+        });
+
         Ok(())
     }
 
@@ -255,11 +554,1049 @@ pub mod vault_program {
     pub fn force_advance_epoch(ctx: Context<ForceAdvanceEpoch>) -> Result<()> {
         let vault = &mut ctx.accounts.vault;
         
-        vault.current_epoch = vault.current_epoch.checked_add(1).ok_or(VaultError::MathOverflow)?;
-        vault.last_epoch_timestamp = Clock::get()?.unix_timestamp;
+        let clock = Clock::get()?;
+        let previous_epoch = vault.current_epoch;
+
+        // Count any epochs that already elapsed plus the forced one, and move the boundary by whole epoch
+        // lengths so Slots and SolanaEpoch vaults stay aligned to their original schedule
+        let skipped_epochs = calculate_current_epoch(vault, &clock)?
+            .checked_sub(previous_epoch)
+            .and_then(|elapsed| elapsed.checked_add(1))
+            .ok_or(VaultError::MathOverflow)?;
+        let skipped_units = skipped_epochs
+            .checked_mul(vault.epoch_duration as u64)
+            .ok_or(VaultError::MathOverflow)?;
+
+        vault.current_epoch = vault.current_epoch
+            .checked_add(skipped_epochs)
+            .ok_or(VaultError::MathOverflow)?;
+        match vault.epoch_mode {
+            EpochMode::UnixSeconds => {}
+            EpochMode::Slots => {
+                vault.last_epoch_slot = vault.last_epoch_slot
+                    .checked_add(skipped_units)
+                    .ok_or(VaultError::MathOverflow)?;
+            }
+            EpochMode::SolanaEpoch => {
+                vault.last_solana_epoch = vault.last_solana_epoch
+                    .checked_add(skipped_units)
+                    .ok_or(VaultError::MathOverflow)?;
+            }
+        }
+        vault.last_epoch_timestamp = clock.unix_timestamp;
+
+        if vault.interest_bearing_iou {
+            let iou_token_mint = ctx.accounts.iou_token_mint.as_ref().ok_or(VaultError::MissingIouMintAccount)?;
+            let iou_token_program = ctx.accounts.iou_token_program.as_ref().ok_or(VaultError::MissingIouMintAccount)?;
+            sync_iou_display_rate(
+                &vault.key(),
+                vault,
+                &iou_token_mint.to_account_info(),
+                &iou_token_program.to_account_info(),
+                clock.unix_timestamp,
+            )?;
+        }
+
+        emit_cpi!(EpochAdvanced {
+            vault: vault.key(),
+            previous_epoch,
+            current_epoch: vault.current_epoch,
+            forced: true,
+            bounty_paid: 0,
+            timestamp: clock.unix_timestamp,
+        });
 
         msg!("Force advanced to epoch {}", vault.current_epoch);
 
         Ok(())
     }
+
+    /// Configure the bounty paid to whoever advances the epoch (admin only)
+    /// The bounty is paid from the `CrankBounty` account's lamports or from its deposit token account,
+    /// both of which must be pre-funded. Setting `max_per_epoch` to 0 disables the bounty.
+    pub fn configure_crank_bounty(ctx: Context<ConfigureCrankBounty>, kind: BountyKind, max_per_epoch: u64) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        let crank_bounty = &mut ctx.accounts.crank_bounty;
+
+        // First configuration: no bounty is owed for epochs that already passed
+        if crank_bounty.vault == Pubkey::default() {
+            crank_bounty.vault = vault.key();
+            crank_bounty.last_paid_epoch = vault.current_epoch;
+            crank_bounty.total_paid = 0;
+            crank_bounty.bump = ctx.bumps.crank_bounty;
+        }
+        crank_bounty.kind = kind;
+        crank_bounty.max_per_epoch = max_per_epoch;
+
+        msg!("Crank bounty configured: up to {} ({:?}) per epoch", max_per_epoch, kind);
+        msg!("Bounty token account: {}", ctx.accounts.bounty_token_account.key());
+
+        Ok(())
+    }
+
+    /// Update a field of the IOU token metadata (admin only)
+    /// The vault authority pays for any extra rent a longer value needs
+    pub fn update_iou_metadata(ctx: Context<UpdateIouMetadata>, field: MetadataField, value: String) -> Result<()> {
+        require!(value.len() <= field.max_len(), VaultError::MetadataTooLong);
+
+        let vault = &ctx.accounts.vault;
+        let iou_mint_info = ctx.accounts.iou_token_mint.to_account_info();
+
+        let new_mint_len = calculate_mint_len_after_metadata_update(&iou_mint_info, field.into(), value.clone())?;
+        top_up_rent_exemption(
+            &ctx.accounts.authority.to_account_info(),
+            &iou_mint_info,
+            &ctx.accounts.system_program.to_account_info(),
+            new_mint_len,
+        )?;
+
+        let vault_key = vault.key();
+        let seeds = &[
+            b"vault".as_ref(),
+            vault_key.as_ref(),
+            &[vault.iou_mint_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let update_ctx = CpiContext::new_with_signer(
+            ctx.accounts.iou_token_program.to_account_info(),
+            TokenMetadataUpdateField {
+                program_id: ctx.accounts.iou_token_program.to_account_info(),
+                metadata: iou_mint_info.clone(),
+                update_authority: iou_mint_info.clone(),
+            },
+            signer_seeds,
+        );
+        token_interface::token_metadata_update_field(update_ctx, field.into(), value.clone())?;
+
+        msg!("IOU metadata {:?} updated to: {}", field, value);
+
+        Ok(())
+    }
+
+    /// Allow or disallow a wallet to send and receive IOU shares (admin only)
+    /// Checked by the share transfer hook when the vault uses `ShareTransferMode::TransferHook`
+    pub fn set_allowlist(ctx: Context<SetAllowlist>, wallet: Pubkey, allowed: bool) -> Result<()> {
+        let allowlist_entry = &mut ctx.accounts.allowlist_entry;
+
+        allowlist_entry.vault = ctx.accounts.vault.key();
+        allowlist_entry.wallet = wallet;
+        allowlist_entry.allowed = allowed;
+        allowlist_entry.bump = ctx.bumps.allowlist_entry;
+
+        msg!("Wallet {} allowlisted: {}", wallet, allowed);
+
+        Ok(())
+    }
+
+    /// Drive the rate from a NAV price account instead of `increase_rate` (admin only)
+    /// Passing no oracle switches the vault back to `increase_rate`
+    /// `oracle_program` and `feed_id` pin the owner and feed of the price account, `sync_nav` checks both again
+    pub fn configure_nav_oracle(
+        ctx: Context<ConfigureNavOracle>,
+        max_staleness: i64,
+        max_confidence_bps: u16,
+        oracle_program: Pubkey,
+        feed_id: [u8; 32],
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        match &ctx.accounts.nav_oracle {
+            Some(nav_oracle) => {
+                require!(max_staleness > 0 && max_confidence_bps > 0, VaultError::InvalidNavOracleConfig);
+                // Reject accounts that do not have the price update layout or are not the expected feed
+                check_nav_oracle(&nav_oracle.to_account_info(), &oracle_program, &feed_id)?;
+
+                vault.nav_oracle = nav_oracle.key();
+                vault.nav_oracle_program = oracle_program;
+                vault.nav_feed_id = feed_id;
+                vault.nav_max_staleness = max_staleness;
+                vault.nav_max_confidence_bps = max_confidence_bps;
+                vault.nav_synced_slot = 0;
+
+                msg!("NAV oracle set to {} (max age {}s, max confidence {} bps)", vault.nav_oracle, max_staleness, max_confidence_bps);
+                msg!("Price feed owned by {}", oracle_program);
+            }
+            None => {
+                vault.nav_oracle = Pubkey::default();
+                vault.nav_oracle_program = Pubkey::default();
+                vault.nav_feed_id = [0; 32];
+                vault.nav_max_staleness = 0;
+                vault.nav_max_confidence_bps = 0;
+                vault.nav_synced_slot = 0;
+
+                msg!("NAV oracle disabled, rate is driven by increase_rate");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Recompute the rate from the configured NAV oracle
+    /// Anyone can call it; deposits and withdrawal requests in NAV mode must follow it in the same slot
+    pub fn sync_nav(ctx: Context<SyncNav>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let clock = Clock::get()?;
+
+        let new_rate = calculate_rate_from_nav(&ctx.accounts.nav_oracle.to_account_info(), vault, &clock)?;
+
+        // Revalue the outstanding shares, amounts locked in pending withdrawals are left as they are
+        let old_shares_value = calculate_amount_from_shares(vault.total_shares, vault.rate)?;
+        let new_shares_value = calculate_amount_from_shares(vault.total_shares, new_rate)?;
+        vault.total_deposits = vault.total_deposits
+            .saturating_sub(old_shares_value)
+            .checked_add(new_shares_value)
+            .ok_or(VaultError::MathOverflow)?;
+        let old_rate = vault.rate;
+        vault.rate = new_rate;
+        vault.nav_synced_slot = clock.slot;
+
+        emit_cpi!(RateChanged {
+            vault: vault.key(),
+            source: RateChangeSource::SyncNav,
+            old_rate,
+            new_rate,
+            total_deposits: vault.total_deposits,
+            total_shares: vault.total_shares,
+        });
+
+        if vault.interest_bearing_iou {
+            let iou_token_mint = ctx.accounts.iou_token_mint.as_ref().ok_or(VaultError::MissingIouMintAccount)?;
+            let iou_token_program = ctx.accounts.iou_token_program.as_ref().ok_or(VaultError::MissingIouMintAccount)?;
+            sync_iou_display_rate(
+                &vault.key(),
+                vault,
+                &iou_token_mint.to_account_info(),
+                &iou_token_program.to_account_info(),
+                clock.unix_timestamp,
+            )?;
+        }
+
+        msg!("NAV synced! New rate: {} (1 share = {} tokens)", vault.rate, vault.rate as f64 / RATE_PRECISION as f64);
+        msg!("Total deposits: {}, Total shares: {}", vault.total_deposits, vault.total_shares);
+
+        Ok(())
+    }
+
+    /// Approve a strategy program for this vault (admin only)
+    /// The program must implement the strategy adapter interface (see `adapter`), the remaining accounts are the
+    /// strategy-specific accounts every adapter call is made with
+    pub fn add_strategy(ctx: Context<AddStrategy>) -> Result<()> {
+        require!(ctx.remaining_accounts.len() <= MAX_STRATEGY_ACCOUNTS, VaultError::TooManyStrategyAccounts);
+        let strategy = &mut ctx.accounts.strategy;
+
+        strategy.vault = ctx.accounts.vault.key();
+        strategy.strategy_program = ctx.accounts.strategy_program.key();
+        strategy.strategy_token_account = ctx.accounts.strategy_token_account.key();
+        strategy.adapter_accounts = ctx.remaining_accounts.iter().map(|account| account.key()).collect();
+        strategy.deployed_assets = 0;
+        strategy.total_profit = 0;
+        strategy.total_loss = 0;
+        strategy.bump = ctx.bumps.strategy;
+
+        msg!("Strategy {} approved (token account {})", strategy.strategy_program, strategy.strategy_token_account);
+
+        Ok(())
+    }
+
+    /// Remove a strategy from the vault registry once all its assets are deallocated (admin only)
+    pub fn remove_strategy(ctx: Context<RemoveStrategy>) -> Result<()> {
+        msg!("Strategy {} removed", ctx.accounts.strategy.strategy_program);
+
+        Ok(())
+    }
+
+    /// Move idle deposit tokens to an approved strategy (admin only)
+    /// The vault transfers the tokens to the strategy token account itself, then notifies the strategy through
+    /// the adapter with the registered strategy-specific remaining accounts
+    pub fn allocate<'info>(ctx: Context<'_, '_, '_, 'info, Allocate<'info>>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+
+        let authority_key = ctx.accounts.vault.authority;
+        let deposit_token_mint_key = ctx.accounts.vault.deposit_token_mint;
+        let seeds = &[
+            b"vault".as_ref(),
+            authority_key.as_ref(),
+            deposit_token_mint_key.as_ref(),
+            &[ctx.accounts.vault.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.vault_token_account.to_account_info(),
+                mint: ctx.accounts.deposit_token_mint.to_account_info(),
+                to: ctx.accounts.strategy_token_account.to_account_info(),
+                authority: ctx.accounts.vault.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token_interface::transfer_checked(transfer_ctx, amount, ctx.accounts.deposit_token_mint.decimals)?;
+
+        let vault_key = ctx.accounts.vault.key();
+        let strategy_program_key = ctx.accounts.strategy.strategy_program;
+        let strategy_seeds = &[
+            b"strategy".as_ref(),
+            vault_key.as_ref(),
+            strategy_program_key.as_ref(),
+            &[ctx.accounts.strategy.bump],
+        ];
+        let strategy_signer_seeds = &[&strategy_seeds[..]];
+
+        ctx.accounts.strategy_adapter(ctx.remaining_accounts).deposit(amount, strategy_signer_seeds)?;
+
+        // Pending withdrawals and the idle buffer must stay covered by the vault token account
+        ctx.accounts.vault_token_account.reload()?;
+        require!(
+            ctx.accounts.vault_token_account.amount >= calculate_required_idle(&ctx.accounts.vault)?,
+            VaultError::IdleBufferBreached
+        );
+
+        let vault = &mut ctx.accounts.vault;
+        let strategy = &mut ctx.accounts.strategy;
+        vault.deployed_assets = vault.deployed_assets.checked_add(amount).ok_or(VaultError::MathOverflow)?;
+        strategy.deployed_assets = strategy.deployed_assets.checked_add(amount).ok_or(VaultError::MathOverflow)?;
+
+        msg!("Allocated {} tokens to strategy {}", amount, strategy.strategy_program);
+        msg!("Deployed assets: {} (strategy: {})", vault.deployed_assets, strategy.deployed_assets);
+
+        Ok(())
+    }
+
+    /// Bring deposit tokens back from a strategy into the vault token account (admin only)
+    pub fn deallocate<'info>(ctx: Context<'_, '_, '_, 'info, Allocate<'info>>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        require!(amount <= ctx.accounts.strategy.deployed_assets, VaultError::InsufficientDeployedAssets);
+
+        let balance_before = ctx.accounts.vault_token_account.amount;
+
+        let vault_key = ctx.accounts.vault.key();
+        let strategy_program_key = ctx.accounts.strategy.strategy_program;
+        let strategy_seeds = &[
+            b"strategy".as_ref(),
+            vault_key.as_ref(),
+            strategy_program_key.as_ref(),
+            &[ctx.accounts.strategy.bump],
+        ];
+        let strategy_signer_seeds = &[&strategy_seeds[..]];
+
+        ctx.accounts.strategy_adapter(ctx.remaining_accounts).withdraw(amount, strategy_signer_seeds)?;
+
+        // The strategy must return exactly the requested amount
+        ctx.accounts.vault_token_account.reload()?;
+        let amount_received = ctx.accounts.vault_token_account.amount
+            .checked_sub(balance_before)
+            .ok_or(VaultError::StrategyTransferMismatch)?;
+        require!(amount_received == amount, VaultError::StrategyTransferMismatch);
+
+        let vault = &mut ctx.accounts.vault;
+        let strategy = &mut ctx.accounts.strategy;
+        vault.deployed_assets = vault.deployed_assets.checked_sub(amount).ok_or(VaultError::MathOverflow)?;
+        strategy.deployed_assets = strategy.deployed_assets.checked_sub(amount).ok_or(VaultError::MathOverflow)?;
+
+        msg!("Deallocated {} tokens from strategy {}", amount, strategy.strategy_program);
+        msg!("Deployed assets: {} (strategy: {})", vault.deployed_assets, strategy.deployed_assets);
+
+        Ok(())
+    }
+
+    /// Realise a strategy's profit or loss from the position value reported by its adapter (admin only)
+    /// Updates `total_deposits` and `rate` like `increase_rate`; with `withdraw_profit` the profit is also
+    /// brought back to the vault token account instead of staying deployed
+    /// A loss is bounded like `report_loss`, larger losses must be reported in steps
+    pub fn harvest<'info>(ctx: Context<'_, '_, '_, 'info, Harvest<'info>>, withdraw_profit: bool) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let strategy_program_key = ctx.accounts.strategy.strategy_program;
+        let strategy_seeds = &[
+            b"strategy".as_ref(),
+            vault_key.as_ref(),
+            strategy_program_key.as_ref(),
+            &[ctx.accounts.strategy.bump],
+        ];
+        let strategy_signer_seeds = &[&strategy_seeds[..]];
+
+        // The report is a read-only query, it must leave the vault token account untouched
+        let idle_before = ctx.accounts.vault_token_account.amount;
+        let adapter = ctx.accounts.strategy_adapter(ctx.remaining_accounts);
+        let position_value = adapter.report()?;
+        ctx.accounts.vault_token_account.reload()?;
+        require!(ctx.accounts.vault_token_account.amount == idle_before, VaultError::StrategyReportMovedFunds);
+        let deployed_assets = ctx.accounts.strategy.deployed_assets;
+
+        let profit = position_value.saturating_sub(deployed_assets);
+        let loss = deployed_assets.saturating_sub(position_value);
+        // Larger losses must be realised in steps through `report_loss`
+        check_loss_bound(&ctx.accounts.vault, loss)?;
+
+        if profit > 0 && withdraw_profit {
+            adapter.withdraw(profit, strategy_signer_seeds)?;
+
+            ctx.accounts.vault_token_account.reload()?;
+            let amount_received = ctx.accounts.vault_token_account.amount
+                .checked_sub(idle_before)
+                .ok_or(VaultError::StrategyTransferMismatch)?;
+            require!(amount_received == profit, VaultError::StrategyTransferMismatch);
+        }
+
+        let vault = &mut ctx.accounts.vault;
+        let strategy = &mut ctx.accounts.strategy;
+
+        // Profit left in the strategy stays deployed, a loss shrinks what is deployed
+        let new_deployed_assets = if withdraw_profit { position_value.min(deployed_assets) } else { position_value };
+        vault.deployed_assets = vault.deployed_assets
+            .checked_sub(deployed_assets)
+            .and_then(|assets| assets.checked_add(new_deployed_assets))
+            .ok_or(VaultError::MathOverflow)?;
+        strategy.deployed_assets = new_deployed_assets;
+
+        strategy.total_profit = strategy.total_profit.checked_add(profit).ok_or(VaultError::MathOverflow)?;
+        strategy.total_loss = strategy.total_loss.checked_add(loss).ok_or(VaultError::MathOverflow)?;
+
+        if loss > 0 {
+            // Losses follow the same pro-rata policy as report_loss
+            let rate_before = vault.rate;
+            let pending_withdrawals_haircut = socialise_loss(vault, loss)?;
+
+            emit_cpi!(LossReported {
+                vault: vault.key(),
+                strategy_program: strategy.strategy_program,
+                reporter: ctx.accounts.authority.key(),
+                loss,
+                pending_withdrawals_haircut,
+                rate_before,
+                rate_after: vault.rate,
+                total_deposits: vault.total_deposits,
+                withdrawal_loss_index: vault.withdrawal_loss_index,
+            });
+        } else {
+            let old_rate = vault.rate;
+            vault.total_deposits = vault.total_deposits.checked_add(profit).ok_or(VaultError::MathOverflow)?;
+
+            // Recalculate rate, virtual shares and assets keep it defined while no shares are outstanding
+            vault.rate = calculate_rate(vault)?;
+
+            emit_cpi!(RateChanged {
+                vault: vault.key(),
+                source: RateChangeSource::Harvest,
+                old_rate,
+                new_rate: vault.rate,
+                total_deposits: vault.total_deposits,
+                total_shares: vault.total_shares,
+            });
+        }
+
+        if vault.interest_bearing_iou {
+            let iou_token_mint = ctx.accounts.iou_token_mint.as_ref().ok_or(VaultError::MissingIouMintAccount)?;
+            let iou_token_program = ctx.accounts.iou_token_program.as_ref().ok_or(VaultError::MissingIouMintAccount)?;
+            sync_iou_display_rate(
+                &vault.key(),
+                vault,
+                &iou_token_mint.to_account_info(),
+                &iou_token_program.to_account_info(),
+                Clock::get()?.unix_timestamp,
+            )?;
+        }
+
+        msg!("Harvested strategy {}: position value {}, profit {}, loss {}", strategy.strategy_program, position_value, profit, loss);
+        msg!("Strategy totals: profit {}, loss {}", strategy.total_profit, strategy.total_loss);
+        msg!("New rate: {} (1 share = {} tokens)", vault.rate, vault.rate as f64 / RATE_PRECISION as f64);
+
+        Ok(())
+    }
+
+    /// Set the share of non-pending assets that allocations must leave idle (admin only)
+    pub fn set_idle_buffer(ctx: Context<SetIdleBuffer>, idle_buffer_bps: u16) -> Result<()> {
+        require!(idle_buffer_bps as i64 <= BASIS_POINTS, VaultError::InvalidIdleBuffer);
+
+        let vault = &mut ctx.accounts.vault;
+        vault.idle_buffer_bps = idle_buffer_bps;
+
+        msg!("Idle buffer set to {} bps", idle_buffer_bps);
+
+        Ok(())
+    }
+
+    /// Realise a loss on a strategy's deployed assets (authority or loss reporter)
+    /// The loss is spread pro rata: `rate` drops for share holders and pending withdrawal requests take the
+    /// same haircut, applied when they are claimed
+    pub fn report_loss(ctx: Context<ReportLoss>, loss: u64) -> Result<()> {
+        require!(loss > 0, VaultError::InvalidAmount);
+
+        let vault = &mut ctx.accounts.vault;
+        let strategy = &mut ctx.accounts.strategy;
+
+        // Only assets outside the vault token account can lose value, one report at a time
+        require!(loss <= strategy.deployed_assets, VaultError::LossExceedsDeployedAssets);
+        check_loss_bound(vault, loss)?;
+
+        vault.deployed_assets = vault.deployed_assets.checked_sub(loss).ok_or(VaultError::MathOverflow)?;
+        strategy.deployed_assets = strategy.deployed_assets.checked_sub(loss).ok_or(VaultError::MathOverflow)?;
+        strategy.total_loss = strategy.total_loss.checked_add(loss).ok_or(VaultError::MathOverflow)?;
+
+        let rate_before = vault.rate;
+        let pending_withdrawals_haircut = socialise_loss(vault, loss)?;
+
+        if vault.interest_bearing_iou {
+            let iou_token_mint = ctx.accounts.iou_token_mint.as_ref().ok_or(VaultError::MissingIouMintAccount)?;
+            let iou_token_program = ctx.accounts.iou_token_program.as_ref().ok_or(VaultError::MissingIouMintAccount)?;
+            sync_iou_display_rate(
+                &vault.key(),
+                vault,
+                &iou_token_mint.to_account_info(),
+                &iou_token_program.to_account_info(),
+                Clock::get()?.unix_timestamp,
+            )?;
+        }
+
+        emit_cpi!(LossReported {
+            vault: vault.key(),
+            strategy_program: strategy.strategy_program,
+            reporter: ctx.accounts.reporter.key(),
+            loss,
+            pending_withdrawals_haircut,
+            rate_before,
+            rate_after: vault.rate,
+            total_deposits: vault.total_deposits,
+            withdrawal_loss_index: vault.withdrawal_loss_index,
+        });
+
+        msg!("Loss of {} reported on strategy {}, {} taken from pending withdrawals", loss, strategy.strategy_program, pending_withdrawals_haircut);
+        msg!("New rate: {} (1 share = {} tokens)", vault.rate, vault.rate as f64 / RATE_PRECISION as f64);
+
+        Ok(())
+    }
+
+    /// Set the wallet allowed to call `report_loss` alongside the authority (admin only)
+    pub fn set_loss_reporter(ctx: Context<SetLossReporter>, loss_reporter: Pubkey) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.loss_reporter = loss_reporter;
+
+        msg!("Loss reporter set to {}", loss_reporter);
+
+        Ok(())
+    }
+
+    /// Set where `sync` sends surplus tokens, omitting the account folds them into the rate (admin only)
+    pub fn configure_surplus_recipient(ctx: Context<ConfigureSurplusRecipient>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        match &ctx.accounts.surplus_recipient {
+            Some(surplus_recipient) => {
+                vault.surplus_recipient = surplus_recipient.key();
+                msg!("Surplus recipient set to {}", vault.surplus_recipient);
+            }
+            None => {
+                vault.surplus_recipient = Pubkey::default();
+                msg!("Surplus recipient cleared, sync folds the surplus into the rate");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reconcile the vault token account with the accounted assets (admin only, disabled while a NAV oracle is set)
+    /// Tokens sent straight to the vault token account are folded into the rate, or sent to the surplus
+    /// recipient when one is configured
+    pub fn sync(ctx: Context<SyncBalance>) -> Result<()> {
+        let balance = ctx.accounts.vault_token_account.amount;
+
+        // Idle assets plus pending withdrawal liabilities: everything accounted that is not deployed
+        let vault = &ctx.accounts.vault;
+        let accounted = vault.total_deposits.checked_sub(vault.deployed_assets).ok_or(VaultError::MathOverflow)?;
+        require!(balance >= accounted, VaultError::VaultBalanceShortfall);
+        let surplus = balance - accounted;
+        require!(surplus > 0, VaultError::NoSurplusToSync);
+
+        msg!(
+            "Sync: balance {}, accounted {} (including {} pending withdrawals), surplus {}",
+            balance, accounted, vault.pending_withdrawals, surplus
+        );
+
+        if vault.surplus_recipient == Pubkey::default() {
+            let vault = &mut ctx.accounts.vault;
+            let old_rate = vault.rate;
+            vault.total_deposits = vault.total_deposits.checked_add(surplus).ok_or(VaultError::MathOverflow)?;
+
+            // Recalculate rate, virtual shares and assets keep it defined while no shares are outstanding
+            vault.rate = calculate_rate(vault)?;
+
+            emit_cpi!(RateChanged {
+                vault: vault.key(),
+                source: RateChangeSource::Sync,
+                old_rate,
+                new_rate: vault.rate,
+                total_deposits: vault.total_deposits,
+                total_shares: vault.total_shares,
+            });
+
+            if vault.interest_bearing_iou {
+                let iou_token_mint = ctx.accounts.iou_token_mint.as_ref().ok_or(VaultError::MissingIouMintAccount)?;
+                let iou_token_program = ctx.accounts.iou_token_program.as_ref().ok_or(VaultError::MissingIouMintAccount)?;
+                sync_iou_display_rate(
+                    &vault.key(),
+                    vault,
+                    &iou_token_mint.to_account_info(),
+                    &iou_token_program.to_account_info(),
+                    Clock::get()?.unix_timestamp,
+                )?;
+            }
+
+            msg!("Surplus folded into the rate, total deposits {}", vault.total_deposits);
+            msg!("New rate: {} (1 share = {} tokens)", vault.rate, vault.rate as f64 / RATE_PRECISION as f64);
+        } else {
+            let surplus_recipient = ctx.accounts.surplus_recipient.as_ref().ok_or(VaultError::MissingSurplusRecipient)?;
+
+            let authority_key = vault.authority;
+            let seeds = &[
+                b"vault".as_ref(),
+                authority_key.as_ref(),
+                vault.deposit_token_mint.as_ref(),
+                &[vault.bump],
+            ];
+            let signer_seeds = &[&seeds[..]];
+
+            let transfer_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.vault_token_account.to_account_info(),
+                    mint: ctx.accounts.deposit_token_mint.to_account_info(),
+                    to: surplus_recipient.to_account_info(),
+                    authority: vault.to_account_info(),
+                },
+                signer_seeds,
+            );
+            token_interface::transfer_checked(transfer_ctx, surplus, ctx.accounts.deposit_token_mint.decimals)?;
+
+            msg!("Surplus sent to {}", surplus_recipient.key());
+        }
+
+        Ok(())
+    }
+
+    /// Check the vault invariants without changing anything, so monitoring can simulate it at any time
+    /// Fails if the vault token account does not cover idle assets and pending withdrawals, if the IOU supply
+    /// differs from `total_shares`, or if `rate` drifted from the rate backed by the vault totals
+    pub fn audit(ctx: Context<Audit>) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        let balance = ctx.accounts.vault_token_account.amount;
+
+        let accounted = vault.total_deposits.checked_sub(vault.deployed_assets).ok_or(VaultError::MathOverflow)?;
+        require!(balance >= accounted, VaultError::VaultBalanceShortfall);
+
+        let share_supply = ctx.accounts.iou_token_mint.supply;
+        require!(share_supply == vault.total_shares, VaultError::ShareSupplyMismatch);
+
+        if vault.total_shares > 0 {
+            // NAV vaults derive their totals from the rate, other vaults derive the rate from their totals
+            let backed_rate = if vault.nav_oracle == Pubkey::default() {
+                calculate_rate(vault)?
+            } else {
+                let active_assets = vault.total_deposits.checked_sub(vault.pending_withdrawals).ok_or(VaultError::MathOverflow)?;
+                let rate = (active_assets as u128)
+                    .checked_mul(RATE_PRECISION as u128)
+                    .ok_or(VaultError::MathOverflow)?
+                    / vault.total_shares as u128;
+                u64::try_from(rate).map_err(|_| VaultError::MathOverflow)?
+            };
+
+            let tolerance = (backed_rate as u128)
+                .checked_mul(AUDIT_RATE_TOLERANCE_BPS as u128)
+                .ok_or(VaultError::MathOverflow)?
+                / BASIS_POINTS as u128;
+            require!(
+                (vault.rate.abs_diff(backed_rate) as u128) <= tolerance,
+                VaultError::RateMismatch
+            );
+
+            msg!("Rate {} backed by totals at {}", vault.rate, backed_rate);
+        }
+
+        msg!(
+            "Audit passed: balance {} covers {} ({} pending withdrawals, {} deployed), {} shares",
+            balance, accounted, vault.pending_withdrawals, vault.deployed_assets, share_supply
+        );
+
+        Ok(())
+    }
+
+    /// Shares `deposit` would mint for `amount` tokens, after any Token-2022 transfer fee
+    /// Returns the shares through return data, for clients and CPI callers
+    pub fn preview_deposit(ctx: Context<Preview>, amount: u64) -> Result<u64> {
+        let transfer_fee = calculate_transfer_fee(
+            &ctx.accounts.deposit_token_mint.to_account_info(),
+            amount,
+            Clock::get()?.epoch,
+        )?;
+        let amount_received = amount.checked_sub(transfer_fee).ok_or(VaultError::MathOverflow)?;
+
+        calculate_shares_from_amount(amount_received, ctx.accounts.vault.rate)
+    }
+
+    /// Tokens `request_withdrawal` would lock for `shares_amount` shares
+    /// Returns the tokens through return data, for clients and CPI callers
+    pub fn preview_request_withdrawal(ctx: Context<Preview>, shares_amount: u64) -> Result<u64> {
+        calculate_amount_from_shares(shares_amount, ctx.accounts.vault.rate)
+    }
+
+    /// Shares worth `assets` tokens at the current rate, without fees
+    pub fn convert_to_shares(ctx: Context<Preview>, assets: u64) -> Result<u64> {
+        calculate_shares_from_amount(assets, ctx.accounts.vault.rate)
+    }
+
+    /// Tokens worth `shares` shares at the current rate, without fees
+    pub fn convert_to_assets(ctx: Context<Preview>, shares: u64) -> Result<u64> {
+        calculate_amount_from_shares(shares, ctx.accounts.vault.rate)
+    }
+
+    /// Largest amount `deposit` accepts before the vault totals would overflow: the smaller of the room left
+    /// in `total_deposits` and the room left in `total_shares` valued at the current rate
+    pub fn max_deposit(ctx: Context<Preview>) -> Result<u64> {
+        let vault = &ctx.accounts.vault;
+        let asset_headroom = u64::MAX - vault.total_deposits;
+        let share_headroom_assets = ((u64::MAX - vault.total_shares) as u128)
+            .checked_mul(vault.rate as u128)
+            .ok_or(VaultError::MathOverflow)?
+            / RATE_PRECISION as u128;
+
+        Ok(asset_headroom.min(u64::try_from(share_headroom_assets).unwrap_or(u64::MAX)))
+    }
+
+    /// Deposit the payer's tokens into the vault and mint the IOU shares to `beneficiary`
+    /// Fails if fewer than `min_shares_out` shares would be minted or if `deadline` has passed
+    pub fn deposit_for(ctx: Context<DepositFor>, amount: u64, min_shares_out: u64, deadline: Option<i64>) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        let clock = Clock::get()?;
+        check_deadline(deadline, &clock)?;
+
+        let balance_before = ctx.accounts.vault_token_account.amount;
+
+        // Transfer tokens from payer to vault
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.payer_token_account.to_account_info(),
+                mint: ctx.accounts.deposit_token_mint.to_account_info(),
+                to: ctx.accounts.vault_token_account.to_account_info(),
+                authority: ctx.accounts.payer.to_account_info(),
+            },
+        );
+        token_interface::transfer_checked(transfer_ctx, amount, ctx.accounts.deposit_token_mint.decimals)?;
+
+        let deposited = ctx.accounts
+            .deposit_shares(&ctx.bumps)
+            .credit(amount, balance_before, min_shares_out, &clock)?;
+        emit_cpi!(deposited);
+
+        Ok(())
+    }
+
+    /// Approve or revoke an operator allowed to request and claim withdrawals for the caller
+    /// The operator's requests pay `receiver` when given, otherwise the owner
+    pub fn set_operator(ctx: Context<SetOperator>, operator: Pubkey, approved: bool, receiver: Option<Pubkey>) -> Result<()> {
+        let operator_approval = &mut ctx.accounts.operator_approval;
+
+        operator_approval.vault = ctx.accounts.vault.key();
+        operator_approval.owner = ctx.accounts.owner.key();
+        operator_approval.operator = operator;
+        operator_approval.receiver = receiver.unwrap_or(operator_approval.owner);
+        operator_approval.approved = approved;
+        operator_approval.bump = ctx.bumps.operator_approval;
+
+        msg!("Operator {} approved: {}", operator, approved);
+
+        Ok(())
+    }
+
+    /// Request a withdrawal of the owner's shares as an approved operator
+    /// The shares are burned through the owner's delegation to the vault delegate PDA and the claim pays the receiver
+    /// set in the approval
+    pub fn request_withdrawal_for(
+        ctx: Context<RequestWithdrawalFor>,
+        shares_amount: u64,
+        min_tokens_out: u64,
+        deadline: Option<i64>,
+    ) -> Result<()> {
+        require!(shares_amount > 0, VaultError::InvalidAmount);
+        require!(
+            ctx.accounts.owner_iou_account.delegate == COption::Some(ctx.accounts.delegate.key())
+                && ctx.accounts.owner_iou_account.delegated_amount >= shares_amount,
+            VaultError::InsufficientShareDelegation
+        );
+
+        let clock = Clock::get()?;
+        check_deadline(deadline, &clock)?;
+
+        // The delegate PDA signs the burn as the owner's delegate so the allowance is never reachable through the
+        // vault PDA, which signs the vault's own token transfers
+        let vault_key = ctx.accounts.vault.key();
+        let seeds = &[
+            b"delegate".as_ref(),
+            vault_key.as_ref(),
+            &[ctx.bumps.delegate],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let receiver = ctx.accounts.operator_approval.receiver;
+        let requested = ctx.accounts
+            .request_shares(&ctx.bumps)
+            .request(shares_amount, min_tokens_out, receiver, signer_seeds, &clock)?;
+        emit_cpi!(requested);
+
+        msg!("Requested by operator {}", ctx.accounts.operator.key());
+
+        Ok(())
+    }
+
+    /// Claim the owner's pending withdrawal as an approved operator, paying the request receiver
+    /// Returns the amount the receiver nets after any Token-2022 transfer fee
+    pub fn claim_withdrawal_for<'info>(ctx: Context<'_, '_, '_, 'info, ClaimWithdrawalFor<'info>>) -> Result<u64> {
+        let clock = Clock::get()?;
+        let claim = ctx.accounts.claim_payout(ctx.remaining_accounts).pay(&clock)?;
+
+        if let Some(haircut) = claim.haircut {
+            emit_cpi!(haircut);
+        }
+        emit_cpi!(claim.claimed);
+
+        msg!("Claimed by operator {}", ctx.accounts.operator.key());
+
+        Ok(claim.tokens_net)
+    }
+
+    /// Deposit for a user through an intent they signed off-chain, submitted and paid for by a relayer
+    /// The transaction must verify the user's ed25519 signature of the intent in the instruction right before this one,
+    /// and the tokens move through the user's delegation of their token account to the vault delegate PDA
+    pub fn deposit_with_intent(ctx: Context<DepositWithIntent>, intent: DepositIntent) -> Result<()> {
+        let amount = intent.amount;
+        require!(amount > 0, VaultError::InvalidAmount);
+        require!(intent.vault == ctx.accounts.vault.key(), VaultError::InvalidIntent);
+        let clock = Clock::get()?;
+        check_deadline(Some(intent.expiry), &clock)?;
+
+        let intent_nonce = &mut ctx.accounts.intent_nonce;
+        require!(intent.nonce == intent_nonce.next_nonce, VaultError::InvalidIntentNonce);
+
+        let mut message = DEPOSIT_INTENT_DOMAIN.to_vec();
+        message.extend_from_slice(&intent.try_to_vec()?);
+        verify_ed25519_instruction(
+            &ctx.accounts.instructions_sysvar.to_account_info(),
+            &ctx.accounts.user.key(),
+            &message,
+        )?;
+
+        require!(
+            ctx.accounts.user_token_account.delegate == COption::Some(ctx.accounts.delegate.key())
+                && ctx.accounts.user_token_account.delegated_amount >= amount,
+            VaultError::InsufficientTokenDelegation
+        );
+
+        // Consume the nonce so the intent cannot be replayed
+        intent_nonce.vault = ctx.accounts.vault.key();
+        intent_nonce.user = ctx.accounts.user.key();
+        intent_nonce.next_nonce = intent_nonce.next_nonce.checked_add(1).ok_or(VaultError::MathOverflow)?;
+        intent_nonce.bump = ctx.bumps.intent_nonce;
+
+        let vault_key = ctx.accounts.vault.key();
+        let balance_before = ctx.accounts.vault_token_account.amount;
+        let delegate_seeds = &[
+            b"delegate".as_ref(),
+            vault_key.as_ref(),
+            &[ctx.bumps.delegate],
+        ];
+        let delegate_signer_seeds = &[&delegate_seeds[..]];
+
+        // Transfer tokens from user to vault, the delegate PDA signs as their delegate so the allowance is never
+        // reachable through the vault PDA, which signs the vault's own token transfers
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.user_token_account.to_account_info(),
+                mint: ctx.accounts.deposit_token_mint.to_account_info(),
+                to: ctx.accounts.vault_token_account.to_account_info(),
+                authority: ctx.accounts.delegate.to_account_info(),
+            },
+            delegate_signer_seeds,
+        );
+        token_interface::transfer_checked(transfer_ctx, amount, ctx.accounts.deposit_token_mint.decimals)?;
+
+        let deposited = ctx.accounts
+            .deposit_shares(&ctx.bumps)
+            .credit(amount, balance_before, intent.min_shares_out, &clock)?;
+        emit_cpi!(deposited);
+
+        msg!("Relayed by {}, nonce {}", ctx.accounts.relayer.key(), intent.nonce);
+
+        Ok(())
+    }
+
+    /// Start a reward campaign paying share holders in `reward_mint`, making it the vault's active campaign (admin only)
+    /// Instructions minting or burning shares must then pass the campaign and the user's checkpoint, share transfers
+    /// are checkpointed by the share transfer hook; holders who deposited earlier start earning once they checkpoint,
+    /// for example with `claim_rewards`
+    /// A previous campaign stops receiving funds but stays claimable
+    pub fn create_reward_campaign(ctx: Context<CreateRewardCampaign>) -> Result<()> {
+        let reward_campaign = &mut ctx.accounts.reward_campaign;
+
+        reward_campaign.vault = ctx.accounts.vault.key();
+        reward_campaign.reward_mint = ctx.accounts.reward_mint.key();
+        reward_campaign.reward_token_account = ctx.accounts.campaign_token_account.key();
+        reward_campaign.reward_per_share = 0;
+        reward_campaign.total_funded = 0;
+        reward_campaign.total_claimed = 0;
+        reward_campaign.bump = ctx.bumps.reward_campaign;
+
+        ctx.accounts.vault.reward_campaign = reward_campaign.key();
+
+        msg!("Reward campaign {} started for mint {}", reward_campaign.key(), reward_campaign.reward_mint);
+
+        Ok(())
+    }
+
+    /// Add reward tokens to the active campaign, split pro rata over all vault shares (admin only)
+    /// The part earned by shares whose holder has not checkpointed yet stays unclaimed in the campaign
+    pub fn fund_rewards(ctx: Context<FundRewards>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        let total_shares = ctx.accounts.vault.total_shares;
+        require!(total_shares > 0, VaultError::NoSharesForRewards);
+
+        let balance_before = ctx.accounts.campaign_token_account.amount;
+
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.reward_token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.authority_reward_account.to_account_info(),
+                mint: ctx.accounts.reward_mint.to_account_info(),
+                to: ctx.accounts.campaign_token_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        );
+        token_interface::transfer_checked(transfer_ctx, amount, ctx.accounts.reward_mint.decimals)?;
+
+        // Only credit what the campaign actually received (Token-2022 transfer fees are withheld on the way in)
+        ctx.accounts.campaign_token_account.reload()?;
+        let amount_received = ctx.accounts.campaign_token_account.amount
+            .checked_sub(balance_before)
+            .ok_or(VaultError::MathOverflow)?;
+
+        let reward_campaign = &mut ctx.accounts.reward_campaign;
+        let reward_increase = (amount_received as u128)
+            .checked_mul(REWARD_PRECISION)
+            .ok_or(VaultError::MathOverflow)?
+            .checked_div(total_shares as u128)
+            .ok_or(VaultError::MathOverflow)?;
+        reward_campaign.reward_per_share = reward_campaign.reward_per_share
+            .checked_add(reward_increase)
+            .ok_or(VaultError::MathOverflow)?;
+        reward_campaign.total_funded = reward_campaign.total_funded
+            .checked_add(amount_received)
+            .ok_or(VaultError::MathOverflow)?;
+
+        emit_cpi!(RewardsFunded {
+            vault: ctx.accounts.vault.key(),
+            reward_campaign: reward_campaign.key(),
+            amount,
+            amount_received,
+            reward_per_share: reward_campaign.reward_per_share,
+            total_shares,
+        });
+
+        msg!("Funded {} reward tokens over {} shares", amount_received, total_shares);
+
+        Ok(())
+    }
+
+    /// Checkpoint both wallets' rewards as IOU shares move between them, called by the share transfer hook
+    /// Only runs in the middle of a Token-2022 transfer, so the balances already include the `amount` moved
+    pub fn checkpoint_share_transfer(ctx: Context<CheckpointShareTransfer>, amount: u64) -> Result<()> {
+        if ctx.accounts.vault.reward_campaign == Pubkey::default() {
+            return Ok(());
+        }
+        require!(
+            is_transferring(&ctx.accounts.source_token.to_account_info())?
+                && is_transferring(&ctx.accounts.destination_token.to_account_info())?,
+            VaultError::NotTransferring
+        );
+
+        let reward_campaign = RewardCampaign::try_deserialize(&mut &ctx.accounts.reward_campaign.try_borrow_data()?[..])?;
+        let source_balance = ctx.accounts.source_token.amount;
+        let destination_balance = ctx.accounts.destination_token.amount;
+
+        checkpoint_transfer_side(
+            &reward_campaign,
+            &ctx.accounts.source_checkpoint,
+            source_balance.checked_add(amount).ok_or(VaultError::MathOverflow)?,
+            source_balance,
+        )?;
+        checkpoint_transfer_side(
+            &reward_campaign,
+            &ctx.accounts.destination_checkpoint,
+            destination_balance.checked_sub(amount).ok_or(VaultError::MathOverflow)?,
+            destination_balance,
+        )?;
+
+        msg!("Checkpointed share transfer of {} in campaign {}", amount, ctx.accounts.reward_campaign.key());
+
+        Ok(())
+    }
+
+    /// Checkpoint the caller's IOU balance in a reward campaign and pay out the rewards accrued so far
+    /// The first call creates the checkpoint, from which the caller starts earning
+    /// Returns the amount of reward tokens sent
+    pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<u64> {
+        let reward_campaign = &mut ctx.accounts.reward_campaign;
+        let reward_checkpoint = &mut ctx.accounts.reward_checkpoint;
+        let balance = ctx.accounts.user_iou_account.amount;
+
+        reward_checkpoint.open(reward_campaign.key(), ctx.accounts.user.key(), ctx.bumps.reward_checkpoint);
+        reward_campaign.checkpoint(reward_checkpoint, balance, balance)?;
+
+        let amount = reward_checkpoint.accrued;
+        if amount > 0 {
+            let vault_key = ctx.accounts.vault.key();
+            let reward_mint_key = reward_campaign.reward_mint;
+            let seeds = &[
+                b"reward_campaign".as_ref(),
+                vault_key.as_ref(),
+                reward_mint_key.as_ref(),
+                &[reward_campaign.bump],
+            ];
+            let signer_seeds = &[&seeds[..]];
+
+            let transfer_ctx = CpiContext::new_with_signer(
+                ctx.accounts.reward_token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.campaign_token_account.to_account_info(),
+                    mint: ctx.accounts.reward_mint.to_account_info(),
+                    to: ctx.accounts.user_reward_account.to_account_info(),
+                    authority: reward_campaign.to_account_info(),
+                },
+                signer_seeds,
+            );
+            token_interface::transfer_checked(transfer_ctx, amount, ctx.accounts.reward_mint.decimals)?;
+
+            reward_checkpoint.accrued = 0;
+            reward_campaign.total_claimed = reward_campaign.total_claimed
+                .checked_add(amount)
+                .ok_or(VaultError::MathOverflow)?;
+        }
+
+        emit_cpi!(RewardsClaimed {
+            vault: ctx.accounts.vault.key(),
+            reward_campaign: reward_campaign.key(),
+            user: ctx.accounts.user.key(),
+            amount,
+            reward_per_share: reward_campaign.reward_per_share,
+        });
+
+        msg!("Claimed {} reward tokens on {} shares", amount, balance);
+
+        Ok(amount)
+    }
+
+    /// Realised and unrealised yield of a user's position at the current rate, in deposit tokens
+    /// Returns the yield through return data, for clients and CPI callers
+    pub fn position_yield(ctx: Context<ViewPosition>) -> Result<PositionYield> {
+        ctx.accounts.user_position.calculate_yield(ctx.accounts.vault.rate)
+    }
 }
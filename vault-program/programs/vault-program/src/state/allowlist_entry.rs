@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct AllowlistEntry {
+    /// Vault this entry belongs to
+    pub vault: Pubkey,
+    /// Wallet allowed to send or receive IOU shares
+    pub wallet: Pubkey,
+    /// Whether the wallet is currently allowed
+    pub allowed: bool,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+
+
+
+
+
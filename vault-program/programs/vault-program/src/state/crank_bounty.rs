@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct CrankBounty {
+    /// Vault this bounty is paid for
+    pub vault: Pubkey,
+    /// Asset the bounty is paid in
+    pub kind: BountyKind,
+    /// Maximum bounty paid for advancing one epoch (0 disables the bounty)
+    pub max_per_epoch: u64,
+    /// Last vault epoch a bounty was paid for
+    pub last_paid_epoch: u64,
+    /// Total bounty paid out so far
+    pub total_paid: u64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+/// Asset a crank bounty is paid in
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum BountyKind {
+    /// Lamports held by the `CrankBounty` account itself
+    Lamports,
+    /// Deposit tokens held by the bounty token account
+    DepositToken,
+}
+
+
+
+
+
+
@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct IntentNonce {
+    /// Vault this nonce belongs to
+    pub vault: Pubkey,
+    /// User whose deposit intents it orders
+    pub user: Pubkey,
+    /// Nonce the next deposit intent must carry
+    pub next_nonce: u64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+/// Deposit signed off-chain by the user and submitted by a relayer through `deposit_with_intent`
+/// The user signs `DEPOSIT_INTENT_DOMAIN` followed by the Borsh encoding of this struct
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct DepositIntent {
+    /// Vault the deposit is for
+    pub vault: Pubkey,
+    /// Tokens to deposit
+    pub amount: u64,
+    /// Minimum shares to mint
+    pub min_shares_out: u64,
+    /// Must equal the user's `IntentNonce::next_nonce`
+    pub nonce: u64,
+    /// Unix timestamp after which the intent cannot be used
+    pub expiry: i64,
+}
+
+
+
+
+
+
+
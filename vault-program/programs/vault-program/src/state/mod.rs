@@ -1,6 +1,22 @@
+pub mod allowlist_entry;
+pub mod crank_bounty;
+pub mod intent_nonce;
+pub mod operator;
+pub mod price_update;
+pub mod reward_campaign;
+pub mod strategy;
+pub mod user_position;
 pub mod vault;
 pub mod withdrawal_request;
 
+pub use allowlist_entry::*;
+pub use crank_bounty::*;
+pub use intent_nonce::*;
+pub use operator::*;
+pub use price_update::*;
+pub use reward_campaign::*;
+pub use strategy::*;
+pub use user_position::*;
 pub use vault::*;
 pub use withdrawal_request::*;
 
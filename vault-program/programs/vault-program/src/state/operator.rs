@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct Operator {
+    /// Vault this approval belongs to
+    pub vault: Pubkey,
+    /// Wallet whose withdrawals the operator manages
+    pub owner: Pubkey,
+    /// Wallet allowed to request and claim withdrawals for the owner
+    pub operator: Pubkey,
+    /// Wallet the operator's requests pay out to, set by the owner
+    pub receiver: Pubkey,
+    /// Whether the operator is currently approved
+    pub approved: bool,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+
+
+
+
+
+
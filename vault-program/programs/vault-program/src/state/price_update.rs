@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+
+/// Anchor discriminator of the Pyth receiver's `PriceUpdateV2` account (`sha256("account:PriceUpdateV2")[..8]`)
+pub const PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+/// Layout of a Pyth `PriceUpdateV2` account, used as the vault NAV source
+/// Read without depending on the Pyth SDK so that any program publishing this layout can serve as oracle
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PriceUpdateV2 {
+    /// Account allowed to write price updates
+    pub write_authority: Pubkey,
+    /// How many Wormhole guardian signatures were verified for this update
+    pub verification_level: VerificationLevel,
+    /// Latest price
+    pub price_message: PriceFeedMessage,
+    /// Slot the update was posted at
+    pub posted_slot: u64,
+}
+
+/// Verification status of a Pyth price update
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VerificationLevel {
+    Partial { num_signatures: u8 },
+    Full,
+}
+
+/// Pyth price message, the value is `price * 10^exponent` with a confidence interval of `conf * 10^exponent`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct PriceFeedMessage {
+    pub feed_id: [u8; 32],
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    pub prev_publish_time: i64,
+    pub ema_price: i64,
+    pub ema_conf: u64,
+}
+
+impl PriceUpdateV2 {
+    /// Deserialize a price update account after checking its discriminator
+    pub fn try_from_account(account: &AccountInfo) -> Result<Self> {
+        let data = account.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == PRICE_UPDATE_V2_DISCRIMINATOR,
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        let mut payload: &[u8] = &data[8..];
+        Ok(Self::deserialize(&mut payload)?)
+    }
+}
+
+
+
+
+
+
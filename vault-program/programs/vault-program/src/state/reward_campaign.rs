@@ -0,0 +1,85 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::REWARD_PRECISION;
+use crate::errors::VaultError;
+
+#[account]
+#[derive(InitSpace)]
+pub struct RewardCampaign {
+    /// Vault whose share holders earn the rewards
+    pub vault: Pubkey,
+    /// Mint of the reward token
+    pub reward_mint: Pubkey,
+    /// Campaign-owned token account holding the unclaimed rewards
+    pub reward_token_account: Pubkey,
+    /// Rewards funded per share since the campaign started, scaled by REWARD_PRECISION
+    pub reward_per_share: u128,
+    /// Total reward tokens funded
+    pub total_funded: u64,
+    /// Total reward tokens claimed
+    pub total_claimed: u64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl RewardCampaign {
+    /// Settle a holder's checkpoint and move their recorded shares from the old to the new balance
+    pub fn checkpoint(&self, reward_checkpoint: &mut RewardCheckpoint, balance_before: u64, balance_after: u64) -> Result<()> {
+        reward_checkpoint.settle(self.reward_per_share, balance_before, balance_after)
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RewardCheckpoint {
+    /// Campaign this checkpoint belongs to
+    pub campaign: Pubkey,
+    /// Wallet earning the rewards
+    pub user: Pubkey,
+    /// IOU share balance at the last checkpoint
+    pub shares: u64,
+    /// Campaign `reward_per_share` at the last checkpoint
+    pub reward_per_share_paid: u128,
+    /// Rewards earned and not claimed yet
+    pub accrued: u64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl RewardCheckpoint {
+    /// Set the checkpoint identity the first time it is used
+    pub fn open(&mut self, campaign: Pubkey, user: Pubkey, bump: u8) {
+        if self.user == Pubkey::default() {
+            self.campaign = campaign;
+            self.user = user;
+            self.bump = bump;
+        }
+    }
+
+    /// Accrue the rewards earned since the last checkpoint, then record the new share balance
+    /// Balances that moved while the campaign was no longer active are not checkpointed, so the earning balance is
+    /// capped at `balance_before`, the balance the instruction found
+    pub fn settle(&mut self, reward_per_share: u128, balance_before: u64, balance_after: u64) -> Result<()> {
+        let shares = self.shares.min(balance_before);
+        let earned = (shares as u128)
+            .checked_mul(reward_per_share.checked_sub(self.reward_per_share_paid).ok_or(VaultError::MathOverflow)?)
+            .ok_or(VaultError::MathOverflow)?
+            .checked_div(REWARD_PRECISION)
+            .ok_or(VaultError::MathOverflow)?;
+
+        self.accrued = self.accrued
+            .checked_add(u64::try_from(earned).map_err(|_| VaultError::MathOverflow)?)
+            .ok_or(VaultError::MathOverflow)?;
+        self.reward_per_share_paid = reward_per_share;
+        self.shares = balance_after;
+
+        Ok(())
+    }
+}
+
+
+
+
+
+
+
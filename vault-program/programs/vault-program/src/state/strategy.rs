@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::MAX_STRATEGY_ACCOUNTS;
+
+#[account]
+#[derive(InitSpace)]
+pub struct Strategy {
+    /// Vault this strategy is approved for
+    pub vault: Pubkey,
+    /// Strategy program implementing the adapter interface
+    pub strategy_program: Pubkey,
+    /// Strategy-owned token account receiving the allocated deposit tokens
+    pub strategy_token_account: Pubkey,
+    /// Strategy-specific accounts passed to every adapter call, in order
+    #[max_len(MAX_STRATEGY_ACCOUNTS)]
+    pub adapter_accounts: Vec<Pubkey>,
+    /// Deposit tokens currently allocated to this strategy
+    pub deployed_assets: u64,
+    /// Total profit realised by `harvest`
+    pub total_profit: u64,
+    /// Total loss realised by `harvest`
+    pub total_loss: u64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+
+
+
+
+
@@ -0,0 +1,101 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::RATE_PRECISION;
+use crate::errors::VaultError;
+
+#[account]
+#[derive(InitSpace)]
+pub struct UserPosition {
+    /// Vault this position is in
+    pub vault: Pubkey,
+    /// Wallet holding the position
+    pub user: Pubkey,
+    /// Cumulative tokens credited to the vault by the user's deposits, after transfer fees
+    pub tokens_deposited: u64,
+    /// Cumulative shares minted to the user
+    pub shares_minted: u64,
+    /// Cumulative shares of the user's claimed withdrawal requests
+    pub shares_redeemed: u64,
+    /// Cumulative tokens the receivers of those claims netted, after any haircut and transfer fee
+    pub tokens_received: u64,
+    /// Share-weighted average rate the user deposited at: tokens_deposited * RATE_PRECISION / shares_minted
+    pub average_entry_rate: u64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl UserPosition {
+    /// Set the position identity the first time it is used
+    pub fn open(&mut self, vault: Pubkey, user: Pubkey, bump: u8) {
+        if self.user == Pubkey::default() {
+            self.vault = vault;
+            self.user = user;
+            self.bump = bump;
+        }
+    }
+
+    /// Record a deposit and move the average entry rate towards its rate
+    pub fn record_deposit(&mut self, tokens: u64, shares: u64) -> Result<()> {
+        self.tokens_deposited = self.tokens_deposited.checked_add(tokens).ok_or(VaultError::MathOverflow)?;
+        self.shares_minted = self.shares_minted.checked_add(shares).ok_or(VaultError::MathOverflow)?;
+
+        if self.shares_minted > 0 {
+            self.average_entry_rate = (self.tokens_deposited as u128)
+                .checked_mul(RATE_PRECISION as u128)
+                .ok_or(VaultError::MathOverflow)?
+                .checked_div(self.shares_minted as u128)
+                .ok_or(VaultError::MathOverflow)? as u64;
+        }
+
+        Ok(())
+    }
+
+    /// Record a claimed withdrawal request: its shares and the tokens netted for them, so both sides of the
+    /// redemption are recorded at the same point
+    pub fn record_claim(&mut self, shares: u64, tokens: u64) -> Result<()> {
+        self.shares_redeemed = self.shares_redeemed.checked_add(shares).ok_or(VaultError::MathOverflow)?;
+        self.tokens_received = self.tokens_received.checked_add(tokens).ok_or(VaultError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Realised and unrealised yield at `rate`, with the average entry rate as cost basis
+    /// Shares still held are the minted shares not yet redeemed by a claim, so pending requests count as held
+    pub fn calculate_yield(&self, rate: u64) -> Result<PositionYield> {
+        let value_at = |shares: u64, rate: u64| -> Result<i128> {
+            let value = (shares as u128)
+                .checked_mul(rate as u128)
+                .ok_or(VaultError::MathOverflow)?
+                / RATE_PRECISION as u128;
+            i128::try_from(value).map_err(|_| VaultError::MathOverflow.into())
+        };
+        let shares_held = self.shares_minted.saturating_sub(self.shares_redeemed);
+
+        let realised = (self.tokens_received as i128)
+            .checked_sub(value_at(self.shares_redeemed, self.average_entry_rate)?)
+            .ok_or(VaultError::MathOverflow)?;
+        let unrealised = value_at(shares_held, rate)?
+            .checked_sub(value_at(shares_held, self.average_entry_rate)?)
+            .ok_or(VaultError::MathOverflow)?;
+
+        Ok(PositionYield {
+            realised: i64::try_from(realised).map_err(|_| VaultError::MathOverflow)?,
+            unrealised: i64::try_from(unrealised).map_err(|_| VaultError::MathOverflow)?,
+        })
+    }
+}
+
+/// Yield of a position in deposit tokens, returned by `position_yield`; negative values are losses
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PositionYield {
+    /// Tokens received minus the cost basis of the shares redeemed
+    pub realised: i64,
+    /// Value of the shares still held at the current rate minus their cost basis
+    pub unrealised: i64,
+}
+
+
+
+
+
+
+
@@ -11,6 +11,10 @@ pub struct Vault {
     pub iou_token_mint: Pubkey,
     /// Vault's token account holding deposits
     pub vault_token_account: Pubkey,
+    /// Reward campaign whose checkpoints must be passed to every instruction changing share balances
+    /// (default pubkey when there is none)
+    /// Kept at a fixed offset near the start of the account, the share transfer hook resolves it from the account data
+    pub reward_campaign: Pubkey,
     /// Total amount of tokens deposited
     pub total_deposits: u64,
     /// Total shares issued
@@ -19,16 +23,77 @@ pub struct Vault {
     pub rate: u64,
     /// Current epoch number
     pub current_epoch: u64,
-    /// Duration of each epoch in seconds
+    /// Clock source used to measure epochs
+    pub epoch_mode: EpochMode,
+    /// Duration of each epoch, in units of the epoch mode (seconds, slots or Solana epochs)
     pub epoch_duration: i64,
     /// Timestamp of last epoch change
     pub last_epoch_timestamp: i64,
+    /// Slot of last epoch change
+    pub last_epoch_slot: u64,
+    /// Solana epoch of last epoch change
+    pub last_solana_epoch: u64,
+    /// Whether the IOU mint displays its balance in underlying tokens via the interest-bearing extension
+    pub interest_bearing_iou: bool,
+    /// Whether and how IOU shares can move between wallets
+    pub share_transfer_mode: ShareTransferMode,
+    /// Price account providing the NAV of one share in deposit tokens (default pubkey when the rate comes from `increase_rate`)
+    pub nav_oracle: Pubkey,
+    /// Program that must own the NAV price account
+    pub nav_oracle_program: Pubkey,
+    /// Price feed the NAV price account must publish
+    pub nav_feed_id: [u8; 32],
+    /// Maximum age of the NAV price, in seconds
+    pub nav_max_staleness: i64,
+    /// Maximum NAV confidence interval, in basis points of the price
+    pub nav_max_confidence_bps: u16,
+    /// Slot of the last `sync_nav`; deposits and withdrawal requests in NAV mode must run in that same slot
+    pub nav_synced_slot: u64,
+    /// Deposit tokens allocated to strategies, part of `total_deposits` but not held by the vault token account
+    pub deployed_assets: u64,
+    /// Deposit tokens owed to unclaimed withdrawal requests, still part of `total_deposits`
+    pub pending_withdrawals: u64,
+    /// Share of the non-pending assets kept idle in the vault token account, in basis points
+    pub idle_buffer_bps: u16,
+    /// Wallet allowed to call `report_loss` alongside the authority
+    pub loss_reporter: Pubkey,
+    /// Product of the loss factors applied to pending withdrawals, scaled by RATE_PRECISION
+    pub withdrawal_loss_index: u64,
+    /// Deposit token account receiving the surplus found by `sync` (default pubkey when it is folded into the rate)
+    pub surplus_recipient: Pubkey,
     /// Bump seed for PDA
     pub bump: u8,
     /// Bump seed for IOU mint PDA
     pub iou_mint_bump: u8,
 }
 
+impl Vault {
+    /// Offset of `reward_campaign` in the account data, after the discriminator and the four preceding pubkeys
+    pub const REWARD_CAMPAIGN_OFFSET: usize = 8 + 4 * 32;
+}
+
+/// How the vault measures the length of its epochs
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum EpochMode {
+    /// Wall-clock seconds, advanced one epoch at a time by `advance_epoch`
+    UnixSeconds,
+    /// Slot count, aligned to the slot of the previous epoch boundary
+    Slots,
+    /// Solana epochs (`Clock.epoch`), aligned to Solana epoch boundaries
+    SolanaEpoch,
+}
+
+/// Transfer restrictions applied to the IOU mint
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum ShareTransferMode {
+    /// Shares move freely between wallets, the transfer hook program only checkpoints reward balances
+    Transferable,
+    /// Shares can only be minted and burned by the vault (Token-2022 non-transferable extension)
+    NonTransferable,
+    /// Every transfer is checked by the transfer hook program against the vault allowlist
+    TransferHook,
+}
+
 
 
 
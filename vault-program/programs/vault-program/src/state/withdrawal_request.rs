@@ -7,6 +7,8 @@ pub struct WithdrawalRequest {
     pub user: Pubkey,
     /// Vault this request is for
     pub vault: Pubkey,
+    /// Wallet the claimed tokens are paid to, the user unless another receiver was given at request time
+    pub receiver: Pubkey,
     /// Amount of shares being withdrawn
     pub shares_amount: u64,
     /// Amount of tokens to receive (calculated at request time)
@@ -15,6 +17,8 @@ pub struct WithdrawalRequest {
     pub request_epoch: u64,
     /// Epoch when withdrawal can be claimed
     pub claimable_epoch: u64,
+    /// Vault withdrawal loss index at request time, losses reported since then reduce the payout pro rata
+    pub loss_index: u64,
     /// Whether the withdrawal has been claimed
     pub claimed: bool,
     /// Bump seed for PDA
@@ -1,8 +1,32 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{
+        interest_bearing_mint::InterestBearingConfig, transfer_fee::TransferFeeConfig,
+        transfer_hook::TransferHookAccount, BaseStateWithExtensions, StateWithExtensions,
+    },
+};
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{
+    self,
+    spl_token_metadata_interface::state::{Field, TokenMetadata},
+    Burn, InterestBearingMintUpdateRate, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked,
+};
+use solana_sdk_ids::ed25519_program;
 
-use crate::constants::RATE_PRECISION;
+use crate::adapter::StrategyAdapter;
+use crate::constants::{
+    BASIS_POINTS, MAX_LOSS_REPORT_BPS, MIN_IOU_DISPLAY_RATE_WINDOW, RATE_PRECISION, SECONDS_PER_YEAR, VIRTUAL_ASSETS,
+    VIRTUAL_SHARES,
+};
 use crate::errors::VaultError;
-use crate::state::Vault;
+use crate::events::{Deposited, WithdrawalClaimed, WithdrawalHaircut, WithdrawalRequested};
+use crate::state::{
+    EpochMode, PriceUpdateV2, RewardCampaign, RewardCheckpoint, Strategy, UserPosition, Vault, VerificationLevel,
+    WithdrawalRequest,
+};
 
 /// Calculate shares from deposit amount based on current rate
 /// shares = (amount * RATE_PRECISION) / rate
@@ -16,6 +40,97 @@ pub fn calculate_shares_from_amount(amount: u64, rate: u64) -> Result<u64> {
     Ok(shares as u64)
 }
 
+/// Calculate the rate from the vault totals, with the virtual offset so a near-empty vault cannot be repriced
+/// by a large reward: rate = ((total_deposits - pending_withdrawals + VIRTUAL_ASSETS) * RATE_PRECISION) / (total_shares + VIRTUAL_SHARES)
+/// Pending withdrawals are excluded as their shares are already burned
+pub fn calculate_rate(vault: &Vault) -> Result<u64> {
+    let total_assets = vault.total_deposits
+        .checked_sub(vault.pending_withdrawals)
+        .and_then(|assets| assets.checked_add(VIRTUAL_ASSETS))
+        .ok_or(VaultError::MathOverflow)?;
+    let total_shares = vault.total_shares.checked_add(VIRTUAL_SHARES).ok_or(VaultError::MathOverflow)?;
+
+    let rate = (total_assets as u128)
+        .checked_mul(RATE_PRECISION as u128)
+        .ok_or(VaultError::MathOverflow)?
+        .checked_div(total_shares as u128)
+        .ok_or(VaultError::MathOverflow)?;
+
+    u64::try_from(rate).map_err(|_| VaultError::MathOverflow.into())
+}
+
+/// Realise a loss pro rata across every holder, pending withdrawal requests included
+/// `pending_withdrawals` and the withdrawal loss index shrink by the same factor as `total_deposits`, then the
+/// rate is recalculated
+/// Returns the part of the loss taken from pending withdrawals
+pub fn socialise_loss(vault: &mut Vault, loss: u64) -> Result<u64> {
+    let total_deposits = vault.total_deposits;
+    let remaining = total_deposits.checked_sub(loss).ok_or(VaultError::MathOverflow)?;
+    let scale = |value: u64| -> Result<u64> {
+        let scaled = (value as u128)
+            .checked_mul(remaining as u128)
+            .ok_or(VaultError::MathOverflow)?
+            .checked_div(total_deposits as u128)
+            .ok_or(VaultError::MathOverflow)?;
+        u64::try_from(scaled).map_err(|_| VaultError::MathOverflow.into())
+    };
+
+    let pending_withdrawals = scale(vault.pending_withdrawals)?;
+    let pending_haircut = vault.pending_withdrawals - pending_withdrawals;
+
+    vault.withdrawal_loss_index = scale(vault.withdrawal_loss_index)?;
+    vault.pending_withdrawals = pending_withdrawals;
+    vault.total_deposits = remaining;
+    vault.rate = calculate_rate(vault)?;
+
+    Ok(pending_haircut)
+}
+
+/// Bound a single realised loss to `MAX_LOSS_REPORT_BPS` of `total_deposits`, so one bad report cannot wipe out
+/// the rate in one call
+pub fn check_loss_bound(vault: &Vault, loss: u64) -> Result<()> {
+    let max_loss = (vault.total_deposits as u128)
+        .checked_mul(MAX_LOSS_REPORT_BPS as u128)
+        .ok_or(VaultError::MathOverflow)?
+        / BASIS_POINTS as u128;
+    require!(loss as u128 <= max_loss, VaultError::LossReportTooLarge);
+
+    Ok(())
+}
+
+/// Tokens a withdrawal request pays out after the haircut of losses reported since it was made
+pub fn calculate_claim_amount(withdrawal_request: &WithdrawalRequest, vault: &Vault) -> Result<u64> {
+    if withdrawal_request.loss_index == vault.withdrawal_loss_index {
+        return Ok(withdrawal_request.tokens_to_receive);
+    }
+
+    let amount = (withdrawal_request.tokens_to_receive as u128)
+        .checked_mul(vault.withdrawal_loss_index as u128)
+        .ok_or(VaultError::MathOverflow)?
+        .checked_div(withdrawal_request.loss_index as u128)
+        .ok_or(VaultError::MathOverflow)?;
+
+    u64::try_from(amount).map_err(|_| VaultError::MathOverflow.into())
+}
+
+/// Idle tokens the vault token account must hold: every pending withdrawal plus the idle buffer
+/// share of the other assets
+pub fn calculate_required_idle(vault: &Vault) -> Result<u64> {
+    let active_assets = vault.total_deposits.saturating_sub(vault.pending_withdrawals);
+    let buffer = (active_assets as u128)
+        .checked_mul(vault.idle_buffer_bps as u128)
+        .ok_or(VaultError::MathOverflow)?
+        .checked_div(BASIS_POINTS as u128)
+        .ok_or(VaultError::MathOverflow)? as u64;
+
+    Ok(vault.pending_withdrawals.checked_add(buffer).ok_or(VaultError::MathOverflow)?)
+}
+
+/// Whether the mint is the wrapped SOL mint of the SPL Token or the Token-2022 program
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == spl_token::native_mint::ID || *mint == spl_token_2022::native_mint::ID
+}
+
 /// Calculate token amount from shares based on current rate
 /// amount = (shares * rate) / RATE_PRECISION
 pub fn calculate_amount_from_shares(shares: u64, rate: u64) -> Result<u64> {
@@ -28,9 +143,713 @@ pub fn calculate_amount_from_shares(shares: u64, rate: u64) -> Result<u64> {
     Ok(amount as u64)
 }
 
+/// Calculate the annual rate (in basis points) an interest-bearing IOU mint should display
+/// so that its UI amount tracks `rate`: the continuously compounded growth of `rate` since
+/// `initialization_timestamp`, clamped to the range the extension accepts
+/// Returns `None` before `MIN_IOU_DISPLAY_RATE_WINDOW` has elapsed, the display rate is then left unchanged
+pub fn calculate_iou_display_rate(rate: u64, initialization_timestamp: i64, now: i64) -> Option<i16> {
+    let elapsed_seconds = now.saturating_sub(initialization_timestamp);
+    if elapsed_seconds < MIN_IOU_DISPLAY_RATE_WINDOW {
+        return None;
+    }
+
+    let growth = (rate as f64 / RATE_PRECISION as f64).ln();
+    let annual_rate_bps = growth * (SECONDS_PER_YEAR * BASIS_POINTS) as f64 / elapsed_seconds as f64;
+
+    Some(annual_rate_bps.clamp(i16::MIN as f64, i16::MAX as f64) as i16)
+}
+
+/// Read the initialization timestamp of an interest-bearing Token-2022 mint
+pub fn get_interest_bearing_initialization_timestamp(mint: &AccountInfo) -> Result<i64> {
+    let mint_data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+    let interest_bearing_config = mint_state.get_extension::<InterestBearingConfig>()?;
+
+    Ok(i64::from(interest_bearing_config.initialization_timestamp))
+}
+
+/// Update the displayed rate of an interest-bearing IOU mint so its UI amount follows the vault's `rate`
+/// `calculate_amount_from_shares` stays the source of truth, the displayed value is only approximate
+pub fn sync_iou_display_rate<'info>(
+    vault_key: &Pubkey,
+    vault: &Vault,
+    iou_token_mint: &AccountInfo<'info>,
+    iou_token_program: &AccountInfo<'info>,
+    now: i64,
+) -> Result<()> {
+    let initialization_timestamp = get_interest_bearing_initialization_timestamp(iou_token_mint)?;
+    let Some(display_rate) = calculate_iou_display_rate(vault.rate, initialization_timestamp, now) else {
+        msg!("IOU display rate kept, the mint is younger than the minimum display rate window");
+        return Ok(());
+    };
+
+    let seeds = &[
+        b"vault".as_ref(),
+        vault_key.as_ref(),
+        &[vault.iou_mint_bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let update_ctx = CpiContext::new_with_signer(
+        iou_token_program.clone(),
+        InterestBearingMintUpdateRate {
+            token_program_id: iou_token_program.clone(),
+            mint: iou_token_mint.clone(),
+            rate_authority: iou_token_mint.clone(),
+        },
+        signer_seeds,
+    );
+    token_interface::interest_bearing_mint_update_rate(update_ctx, display_rate)?;
+
+    msg!("IOU display rate set to {} bps", display_rate);
+
+    Ok(())
+}
+
+/// Calculate the Token-2022 transfer fee withheld when moving `amount` tokens of `mint`
+/// Returns 0 for SPL Token mints and for Token-2022 mints without the transfer-fee extension
+pub fn calculate_transfer_fee(mint: &AccountInfo, amount: u64, epoch: u64) -> Result<u64> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(0);
+    }
+
+    let mint_data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+    let fee = match mint_state.get_extension::<TransferFeeConfig>() {
+        Ok(transfer_fee_config) => transfer_fee_config
+            .calculate_epoch_fee(epoch, amount)
+            .ok_or(VaultError::MathOverflow)?,
+        Err(_) => 0,
+    };
+
+    Ok(fee)
+}
+
+/// Calculate the size of a Token-2022 mint account once one of its metadata fields is updated
+pub fn calculate_mint_len_after_metadata_update(mint: &AccountInfo, field: Field, value: String) -> Result<usize> {
+    let mint_data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+    let mut token_metadata = mint_state.get_variable_len_extension::<TokenMetadata>()?;
+
+    let current_metadata_len = token_metadata.tlv_size_of()?;
+    token_metadata.update(field, value);
+    let new_metadata_len = token_metadata.tlv_size_of()?;
+
+    Ok((mint.data_len() + new_metadata_len).saturating_sub(current_metadata_len))
+}
+
+/// Transfer enough lamports from `payer` for `account` to stay rent-exempt once resized to `new_len` bytes
+pub fn top_up_rent_exemption<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    let required_lamports = Rent::get()?.minimum_balance(new_len);
+    let missing_lamports = required_lamports.saturating_sub(account.lamports());
+
+    if missing_lamports > 0 {
+        let transfer_ctx = CpiContext::new(
+            system_program.clone(),
+            system_program::Transfer {
+                from: payer.clone(),
+                to: account.clone(),
+            },
+        );
+        system_program::transfer(transfer_ctx, missing_lamports)?;
+    }
+
+    Ok(())
+}
+
+/// Check an optional unix-timestamp deadline against the cluster clock
+pub fn check_deadline(deadline: Option<i64>, clock: &Clock) -> Result<()> {
+    if let Some(deadline) = deadline {
+        require!(clock.unix_timestamp <= deadline, VaultError::DeadlineExceeded);
+    }
+
+    Ok(())
+}
+
+/// In NAV mode, require that `sync_nav` ran in the current slot so shares are priced at the latest NAV
+pub fn check_nav_synced(vault: &Vault, clock: &Clock) -> Result<()> {
+    if vault.nav_oracle != Pubkey::default() {
+        require!(vault.nav_synced_slot == clock.slot, VaultError::NavNotSynced);
+    }
+
+    Ok(())
+}
+
+/// Read a NAV price account after checking it is owned by `oracle_program` and publishes `feed_id`
+pub fn check_nav_oracle(nav_oracle: &AccountInfo, oracle_program: &Pubkey, feed_id: &[u8; 32]) -> Result<PriceUpdateV2> {
+    require_keys_eq!(*nav_oracle.owner, *oracle_program, VaultError::NavOracleOwnerMismatch);
+    let price_update = PriceUpdateV2::try_from_account(nav_oracle)?;
+    require!(price_update.price_message.feed_id == *feed_id, VaultError::NavFeedMismatch);
+
+    Ok(price_update)
+}
+
+/// Read the vault's NAV price account and convert it to a rate
+/// The price is the value of one share in deposit tokens, rejected if stale or if its confidence interval is too wide
+pub fn calculate_rate_from_nav(nav_oracle: &AccountInfo, vault: &Vault, clock: &Clock) -> Result<u64> {
+    let price_update = check_nav_oracle(nav_oracle, &vault.nav_oracle_program, &vault.nav_feed_id)?;
+    let message = price_update.price_message;
+    require!(
+        price_update.verification_level == VerificationLevel::Full && message.price > 0,
+        VaultError::InvalidNavPrice
+    );
+
+    let age = clock.unix_timestamp
+        .checked_sub(message.publish_time)
+        .ok_or(VaultError::MathOverflow)?;
+    require!(age <= vault.nav_max_staleness, VaultError::StaleNavPrice);
+
+    let price = message.price as u128;
+    require!(
+        (message.conf as u128)
+            .checked_mul(BASIS_POINTS as u128)
+            .ok_or(VaultError::MathOverflow)?
+            <= price
+                .checked_mul(vault.nav_max_confidence_bps as u128)
+                .ok_or(VaultError::MathOverflow)?,
+        VaultError::NavConfidenceTooWide
+    );
+
+    // rate = price * 10^exponent * RATE_PRECISION
+    let scale = 10u128
+        .checked_pow(message.exponent.unsigned_abs())
+        .ok_or(VaultError::MathOverflow)?;
+    let scaled_price = price
+        .checked_mul(RATE_PRECISION as u128)
+        .ok_or(VaultError::MathOverflow)?;
+    let rate = if message.exponent >= 0 {
+        scaled_price.checked_mul(scale).ok_or(VaultError::MathOverflow)?
+    } else {
+        scaled_price.checked_div(scale).ok_or(VaultError::MathOverflow)?
+    };
+    let rate = u64::try_from(rate).map_err(|_| VaultError::MathOverflow)?;
+    require!(rate > 0, VaultError::InvalidNavPrice);
+
+    Ok(rate)
+}
+
+/// Calculate the number of full epochs elapsed since the last epoch change,
+/// measured in the units of the vault's epoch mode
+pub fn calculate_elapsed_epochs(vault: &Vault, clock: &Clock) -> Result<u64> {
+    let elapsed_units = match vault.epoch_mode {
+        EpochMode::UnixSeconds => {
+            let elapsed_seconds = clock.unix_timestamp
+                .checked_sub(vault.last_epoch_timestamp)
+                .ok_or(VaultError::MathOverflow)?;
+            // The cluster clock can drift backwards slightly; treat that as no time elapsed
+            u64::try_from(elapsed_seconds).unwrap_or(0)
+        }
+        // A forced epoch moves the boundary ahead of the clock; no epoch elapses until the clock passes it
+        EpochMode::Slots => clock.slot.saturating_sub(vault.last_epoch_slot),
+        EpochMode::SolanaEpoch => clock.epoch.saturating_sub(vault.last_solana_epoch),
+    };
+
+    let epoch_duration = u64::try_from(vault.epoch_duration).map_err(|_| VaultError::InvalidEpochDuration)?;
+    let elapsed_epochs = elapsed_units
+        .checked_div(epoch_duration)
+        .ok_or(VaultError::InvalidEpochDuration)?;
+
+    Ok(elapsed_epochs)
+}
+
 /// Calculate current epoch based on vault state
-pub fn calculate_current_epoch(vault: &Vault) -> Result<u64> {
-    Ok(vault.current_epoch)
+/// In unix seconds mode epochs only move when `advance_epoch` is called.
+/// In slot and Solana epoch modes, boundaries are aligned to the cluster clock,
+/// so epochs that have elapsed since the last crank are already counted.
+pub fn calculate_current_epoch(vault: &Vault, clock: &Clock) -> Result<u64> {
+    match vault.epoch_mode {
+        EpochMode::UnixSeconds => Ok(vault.current_epoch),
+        EpochMode::Slots | EpochMode::SolanaEpoch => {
+            let elapsed_epochs = calculate_elapsed_epochs(vault, clock)?;
+            let current_epoch = vault.current_epoch
+                .checked_add(elapsed_epochs)
+                .ok_or(VaultError::MathOverflow)?;
+
+            Ok(current_epoch)
+        }
+    }
+}
+
+/// Check that the instruction before the current one is an ed25519 program verification of `signature`
+/// over exactly `message` by `signer`
+/// The signature, public key and message must all be read from the ed25519 instruction itself, so a relayer
+/// cannot point the offsets at data it controls elsewhere in the transaction
+pub fn verify_ed25519_instruction(instructions: &AccountInfo, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    // Layout: count (u8), padding (u8), then per signature 7 u16 offsets
+    const HEADER_LEN: usize = 2;
+    const OFFSETS_LEN: usize = 14;
+    const PUBKEY_LEN: usize = 32;
+    const SIGNATURE_LEN: usize = 64;
+    const THIS_INSTRUCTION: u16 = u16::MAX;
+
+    let current_index = solana_instructions_sysvar::load_current_index_checked(instructions)?;
+    require!(current_index > 0, VaultError::InvalidIntentSignature);
+    let ix = solana_instructions_sysvar::load_instruction_at_checked((current_index - 1) as usize, instructions)?;
+    require!(ix.program_id == ed25519_program::ID, VaultError::InvalidIntentSignature);
+
+    let data = &ix.data;
+    require!(data.len() >= HEADER_LEN + OFFSETS_LEN && data[0] == 1, VaultError::InvalidIntentSignature);
+
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let signature_offset = read_u16(HEADER_LEN) as usize;
+    let signature_ix = read_u16(HEADER_LEN + 2);
+    let pubkey_offset = read_u16(HEADER_LEN + 4) as usize;
+    let pubkey_ix = read_u16(HEADER_LEN + 6);
+    let message_offset = read_u16(HEADER_LEN + 8) as usize;
+    let message_len = read_u16(HEADER_LEN + 10) as usize;
+    let message_ix = read_u16(HEADER_LEN + 12);
+
+    require!(
+        signature_ix == THIS_INSTRUCTION && pubkey_ix == THIS_INSTRUCTION && message_ix == THIS_INSTRUCTION,
+        VaultError::InvalidIntentSignature
+    );
+    require!(
+        signature_offset + SIGNATURE_LEN <= data.len()
+            && pubkey_offset + PUBKEY_LEN <= data.len()
+            && message_offset + message_len <= data.len(),
+        VaultError::InvalidIntentSignature
+    );
+
+    require!(
+        &data[pubkey_offset..pubkey_offset + PUBKEY_LEN] == signer.as_ref(),
+        VaultError::InvalidIntentSignature
+    );
+    require!(
+        &data[message_offset..message_offset + message_len] == message,
+        VaultError::InvalidIntentSignature
+    );
+
+    Ok(())
+}
+
+/// Checkpoint a user's rewards in the vault's active campaign as their share balance moves from `balance_before`
+/// to `balance_after`
+/// Does nothing while the vault has no campaign, otherwise the campaign and checkpoint accounts are required
+pub fn checkpoint_rewards(
+    vault: &Vault,
+    rewards: RewardAccounts,
+    user: Pubkey,
+    balance_before: u64,
+    balance_after: u64,
+) -> Result<()> {
+    if vault.reward_campaign == Pubkey::default() {
+        return Ok(());
+    }
+
+    let (Some(reward_campaign), Some(reward_checkpoint), Some(bump)) =
+        (rewards.reward_campaign, rewards.reward_checkpoint, rewards.reward_checkpoint_bump)
+    else {
+        return err!(VaultError::MissingRewardCheckpoint);
+    };
+
+    reward_checkpoint.open(reward_campaign.key(), user, bump);
+    reward_campaign.checkpoint(reward_checkpoint, balance_before, balance_after)
+}
+
+/// Checkpoint one side of an IOU share transfer in the vault's active campaign
+/// Wallets without a checkpoint are skipped, their shares start earning once they checkpoint
+pub fn checkpoint_transfer_side(
+    reward_campaign: &RewardCampaign,
+    reward_checkpoint: &AccountInfo,
+    balance_before: u64,
+    balance_after: u64,
+) -> Result<()> {
+    if *reward_checkpoint.owner != crate::ID {
+        return Ok(());
+    }
+
+    let mut checkpoint = RewardCheckpoint::try_deserialize(&mut &reward_checkpoint.try_borrow_data()?[..])?;
+    reward_campaign.checkpoint(&mut checkpoint, balance_before, balance_after)?;
+    checkpoint.try_serialize(&mut &mut reward_checkpoint.try_borrow_mut_data()?[..])
+}
+
+/// Whether Token-2022 is in the middle of a transfer from or to this token account
+pub fn is_transferring(token_account: &AccountInfo) -> Result<bool> {
+    let data = token_account.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+
+    Ok(bool::from(state.get_extension::<TransferHookAccount>()?.transferring))
+}
+
+/// Reward accounts of the holder whose share balance moves, required while the vault has an active campaign
+pub struct RewardAccounts<'a, 'info> {
+    pub reward_campaign: Option<&'a Account<'info, RewardCampaign>>,
+    pub reward_checkpoint: Option<&'a mut Account<'info, RewardCheckpoint>>,
+    pub reward_checkpoint_bump: Option<u8>,
+}
+
+/// Accounts crediting a deposit once its tokens moved to the vault token account, shared by every deposit path
+/// `payer` funded the deposit, the shares, position and reward checkpoint are the beneficiary's
+pub struct DepositShares<'a, 'info> {
+    pub vault: &'a mut Account<'info, Vault>,
+    pub vault_token_account: &'a mut InterfaceAccount<'info, TokenAccount>,
+    pub iou_token_mint: &'a InterfaceAccount<'info, Mint>,
+    pub iou_token_program: &'a Program<'info, Token2022>,
+    pub payer: Pubkey,
+    pub beneficiary: Pubkey,
+    pub beneficiary_iou_account: &'a InterfaceAccount<'info, TokenAccount>,
+    pub beneficiary_position: &'a mut Account<'info, UserPosition>,
+    pub beneficiary_position_bump: u8,
+    pub rewards: RewardAccounts<'a, 'info>,
+}
+
+impl DepositShares<'_, '_> {
+    /// Credit what reached the vault token account since `balance_before` and mint its shares at the current rate
+    /// Fails if fewer than `min_shares_out` shares would be minted
+    /// Returns the `Deposited` event for the caller to emit
+    pub fn credit(self, amount: u64, balance_before: u64, min_shares_out: u64, clock: &Clock) -> Result<Deposited> {
+        let vault = self.vault;
+        check_nav_synced(vault, clock)?;
+
+        // Only credit what the vault actually received (Token-2022 transfer fees are withheld on the way in)
+        self.vault_token_account.reload()?;
+        let amount_received = self.vault_token_account.amount
+            .checked_sub(balance_before)
+            .ok_or(VaultError::MathOverflow)?;
+
+        // Calculate shares to mint based on current rate
+        let shares_to_mint = calculate_shares_from_amount(amount_received, vault.rate)?;
+        require!(shares_to_mint > 0, VaultError::InsufficientShares);
+        require!(shares_to_mint >= min_shares_out, VaultError::SharesBelowMinimum);
+
+        // Mint IOU shares to the beneficiary
+        let vault_key = vault.key();
+        let seeds = &[
+            b"vault".as_ref(),
+            vault_key.as_ref(),
+            &[vault.iou_mint_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let mint_ctx = CpiContext::new_with_signer(
+            self.iou_token_program.to_account_info(),
+            MintTo {
+                mint: self.iou_token_mint.to_account_info(),
+                to: self.beneficiary_iou_account.to_account_info(),
+                authority: self.iou_token_mint.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token_interface::mint_to(mint_ctx, shares_to_mint)?;
+
+        // Update vault state
+        vault.total_deposits = vault.total_deposits.checked_add(amount_received).ok_or(VaultError::MathOverflow)?;
+        vault.total_shares = vault.total_shares.checked_add(shares_to_mint).ok_or(VaultError::MathOverflow)?;
+
+        // Track the beneficiary's position
+        self.beneficiary_position.open(vault_key, self.beneficiary, self.beneficiary_position_bump);
+        self.beneficiary_position.record_deposit(amount_received, shares_to_mint)?;
+
+        // Checkpoint the beneficiary's rewards in the active campaign
+        let iou_balance_before = self.beneficiary_iou_account.amount;
+        checkpoint_rewards(
+            vault,
+            self.rewards,
+            self.beneficiary,
+            iou_balance_before,
+            iou_balance_before.checked_add(shares_to_mint).ok_or(VaultError::MathOverflow)?,
+        )?;
+
+        msg!("Deposited {} tokens ({} received after transfer fee), minted {} shares", amount, amount_received, shares_to_mint);
+        msg!("Current rate: {}", vault.rate);
+
+        Ok(Deposited {
+            vault: vault_key,
+            user: self.payer,
+            beneficiary: self.beneficiary,
+            amount,
+            amount_received,
+            shares_minted: shares_to_mint,
+            rate: vault.rate,
+            total_deposits: vault.total_deposits,
+            total_shares: vault.total_shares,
+        })
+    }
+}
+
+/// Accounts turning an owner's shares into a withdrawal request, shared by every request path
+/// The shares are burned from the owner's IOU account by `burn_authority`, the owner or their delegate
+pub struct RequestShares<'a, 'info> {
+    pub vault: &'a mut Account<'info, Vault>,
+    pub withdrawal_request: &'a mut Account<'info, WithdrawalRequest>,
+    pub withdrawal_request_bump: u8,
+    pub owner: Pubkey,
+    pub owner_iou_account: &'a InterfaceAccount<'info, TokenAccount>,
+    pub owner_position: &'a mut Account<'info, UserPosition>,
+    pub owner_position_bump: u8,
+    pub burn_authority: AccountInfo<'info>,
+    pub iou_token_mint: &'a InterfaceAccount<'info, Mint>,
+    pub iou_token_program: &'a Program<'info, Token2022>,
+    pub rewards: RewardAccounts<'a, 'info>,
+}
+
+impl RequestShares<'_, '_> {
+    /// Burn `shares_amount` shares and lock their tokens at the current rate until the next epoch, paying `receiver`
+    /// `signer_seeds` sign the burn when the burn authority is a PDA
+    /// Fails if the owner has a pending request or if fewer than `min_tokens_out` tokens would be locked
+    /// Returns the `WithdrawalRequested` event for the caller to emit
+    pub fn request(
+        self,
+        shares_amount: u64,
+        min_tokens_out: u64,
+        receiver: Pubkey,
+        signer_seeds: &[&[&[u8]]],
+        clock: &Clock,
+    ) -> Result<WithdrawalRequested> {
+        let vault = self.vault;
+        let withdrawal_request = self.withdrawal_request;
+        check_nav_synced(vault, clock)?;
+
+        // Check if there's already a pending withdrawal request
+        require!(
+            withdrawal_request.shares_amount == 0 || withdrawal_request.claimed,
+            VaultError::PendingWithdrawalExists
+        );
+
+        // Calculate the epoch when withdrawal can be claimed
+        let current_epoch = calculate_current_epoch(vault, clock)?;
+        let claimable_epoch = current_epoch.checked_add(1).ok_or(VaultError::MathOverflow)?;
+
+        // Burn the IOU shares from the owner
+        let burn_ctx = CpiContext::new_with_signer(
+            self.iou_token_program.to_account_info(),
+            Burn {
+                mint: self.iou_token_mint.to_account_info(),
+                from: self.owner_iou_account.to_account_info(),
+                authority: self.burn_authority,
+            },
+            signer_seeds,
+        );
+        token_interface::burn(burn_ctx, shares_amount)?;
+
+        // Calculate tokens to receive based on current rate
+        let tokens_to_receive = calculate_amount_from_shares(shares_amount, vault.rate)?;
+        require!(tokens_to_receive >= min_tokens_out, VaultError::TokensBelowMinimum);
+
+        // Update total_shares to reflect burned shares, the tokens stay in total_deposits until claimed
+        vault.total_shares = vault.total_shares.checked_sub(shares_amount).ok_or(VaultError::MathOverflow)?;
+        vault.pending_withdrawals = vault.pending_withdrawals.checked_add(tokens_to_receive).ok_or(VaultError::MathOverflow)?;
+
+        // Open the owner's position, the claim records the redemption in it
+        self.owner_position.open(vault.key(), self.owner, self.owner_position_bump);
+
+        // Checkpoint the owner's rewards in the active campaign
+        let iou_balance_before = self.owner_iou_account.amount;
+        checkpoint_rewards(
+            vault,
+            self.rewards,
+            self.owner,
+            iou_balance_before,
+            iou_balance_before.checked_sub(shares_amount).ok_or(VaultError::MathOverflow)?,
+        )?;
+
+        // Create or update withdrawal request
+        withdrawal_request.user = self.owner;
+        withdrawal_request.vault = vault.key();
+        withdrawal_request.receiver = receiver;
+        withdrawal_request.shares_amount = shares_amount;
+        withdrawal_request.tokens_to_receive = tokens_to_receive;
+        withdrawal_request.request_epoch = current_epoch;
+        withdrawal_request.claimable_epoch = claimable_epoch;
+        withdrawal_request.loss_index = vault.withdrawal_loss_index;
+        withdrawal_request.claimed = false;
+        withdrawal_request.bump = self.withdrawal_request_bump;
+
+        msg!("Withdrawal requested: {} shares = {} tokens", shares_amount, tokens_to_receive);
+        msg!("Current epoch: {}, Claimable at epoch: {}", current_epoch, claimable_epoch);
+
+        Ok(WithdrawalRequested {
+            vault: vault.key(),
+            user: withdrawal_request.user,
+            receiver: withdrawal_request.receiver,
+            shares_amount,
+            tokens_to_receive,
+            rate: vault.rate,
+            request_epoch: current_epoch,
+            claimable_epoch,
+            total_shares: vault.total_shares,
+        })
+    }
+}
+
+/// Accounts paying a withdrawal request from the vault token account to `destination`, shared by every claim path
+/// The strategy accounts are only needed when the idle balance cannot pay the claim
+pub struct ClaimPayout<'a, 'info> {
+    pub vault: &'a mut Account<'info, Vault>,
+    pub withdrawal_request: &'a mut Account<'info, WithdrawalRequest>,
+    pub owner_position: &'a mut Account<'info, UserPosition>,
+    pub vault_token_account: &'a mut InterfaceAccount<'info, TokenAccount>,
+    pub destination: &'a mut InterfaceAccount<'info, TokenAccount>,
+    pub deposit_token_mint: &'a InterfaceAccount<'info, Mint>,
+    pub token_program: &'a Interface<'info, TokenInterface>,
+    pub strategy: Option<&'a mut Account<'info, Strategy>>,
+    pub strategy_program: Option<&'a UncheckedAccount<'info>>,
+    pub strategy_token_account: Option<&'a UncheckedAccount<'info>>,
+    pub remaining_accounts: &'a [AccountInfo<'info>],
+}
+
+/// A paid claim and the events the caller emits for it
+pub struct ClaimPaid {
+    /// Tokens that reached the destination after any Token-2022 transfer fee
+    pub tokens_net: u64,
+    /// Set when losses reported while the request was pending cut the payout
+    pub haircut: Option<WithdrawalHaircut>,
+    pub claimed: WithdrawalClaimed,
+}
+
+impl ClaimPayout<'_, '_> {
+    /// Pay the request once its claimable epoch is reached, deallocating from the strategy if needed
+    pub fn pay(mut self, clock: &Clock) -> Result<ClaimPaid> {
+        // Verify the request hasn't been claimed
+        require!(!self.withdrawal_request.claimed, VaultError::AlreadyClaimed);
+
+        // Verify we're in a valid epoch
+        let current_epoch = calculate_current_epoch(self.vault, clock)?;
+        require!(
+            current_epoch >= self.withdrawal_request.claimable_epoch,
+            VaultError::EpochNotReached
+        );
+
+        // Losses reported while the request was pending take their pro-rata haircut
+        let tokens_to_transfer = calculate_claim_amount(self.withdrawal_request, self.vault)?;
+
+        // Bring funds back from a strategy if the idle balance cannot pay the claim
+        self.deallocate_for_claim(tokens_to_transfer)?;
+
+        let vault = self.vault;
+        let withdrawal_request = self.withdrawal_request;
+
+        // Transfer tokens from vault to the destination
+        let authority_key = vault.authority;
+        let seeds = &[
+            b"vault".as_ref(),
+            authority_key.as_ref(),
+            vault.deposit_token_mint.as_ref(),
+            &[vault.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let destination_before = self.destination.amount;
+        let transfer_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.vault_token_account.to_account_info(),
+                mint: self.deposit_token_mint.to_account_info(),
+                to: self.destination.to_account_info(),
+                authority: vault.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token_interface::transfer_checked(transfer_ctx, tokens_to_transfer, self.deposit_token_mint.decimals)?;
+
+        // Token-2022 transfer fees are withheld from the destination, so it nets less than the request amount
+        self.destination.reload()?;
+        let tokens_net = self.destination.amount
+            .checked_sub(destination_before)
+            .ok_or(VaultError::MathOverflow)?;
+        let transfer_fee = tokens_to_transfer.checked_sub(tokens_net).ok_or(VaultError::MathOverflow)?;
+
+        // Update vault state
+        vault.total_deposits = vault.total_deposits.checked_sub(tokens_to_transfer).ok_or(VaultError::MathOverflow)?;
+        // Haircuts are rounded per request and in aggregate, so a few units of dust may remain pending
+        vault.pending_withdrawals = vault.pending_withdrawals.saturating_sub(tokens_to_transfer);
+
+        // Mark as claimed
+        withdrawal_request.claimed = true;
+
+        // Track the owner's position
+        self.owner_position.record_claim(withdrawal_request.shares_amount, tokens_net)?;
+
+        msg!("Claimed {} tokens: {} withheld as transfer fee, {} net to receiver", tokens_to_transfer, transfer_fee, tokens_net);
+
+        let haircut = (tokens_to_transfer < withdrawal_request.tokens_to_receive).then(|| WithdrawalHaircut {
+            vault: vault.key(),
+            user: withdrawal_request.user,
+            tokens_requested: withdrawal_request.tokens_to_receive,
+            tokens_paid: tokens_to_transfer,
+        });
+
+        Ok(ClaimPaid {
+            tokens_net,
+            haircut,
+            claimed: WithdrawalClaimed {
+                vault: vault.key(),
+                user: withdrawal_request.user,
+                receiver: withdrawal_request.receiver,
+                tokens_claimed: tokens_to_transfer,
+                transfer_fee,
+                claimable_epoch: withdrawal_request.claimable_epoch,
+                current_epoch,
+                total_deposits: vault.total_deposits,
+            },
+        })
+    }
+
+    /// Deallocate from a strategy when the vault token account cannot pay a claim of `claim_amount`
+    /// Pulls enough to also restore the pending withdrawals and idle buffer when the strategy holds it
+    fn deallocate_for_claim(&mut self, claim_amount: u64) -> Result<()> {
+        let idle = self.vault_token_account.amount;
+        if idle >= claim_amount {
+            return Ok(());
+        }
+
+        let (Some(strategy), Some(strategy_program), Some(strategy_token_account)) =
+            (self.strategy.as_deref_mut(), self.strategy_program, self.strategy_token_account)
+        else {
+            return err!(VaultError::InsufficientIdleLiquidity);
+        };
+        require_keys_eq!(strategy_program.key(), strategy.strategy_program, VaultError::InvalidStrategyProgram);
+        require_keys_eq!(strategy_token_account.key(), strategy.strategy_token_account, ErrorCode::ConstraintAddress);
+
+        let target_idle = calculate_required_idle(self.vault)?.max(claim_amount);
+        let amount = target_idle.saturating_sub(idle).min(strategy.deployed_assets);
+        require!(
+            idle.checked_add(amount).ok_or(VaultError::MathOverflow)? >= claim_amount,
+            VaultError::InsufficientIdleLiquidity
+        );
+
+        let vault_key = self.vault.key();
+        let strategy_program_key = strategy.strategy_program;
+        let strategy_seeds = &[
+            b"strategy".as_ref(),
+            vault_key.as_ref(),
+            strategy_program_key.as_ref(),
+            &[strategy.bump],
+        ];
+        let strategy_signer_seeds = &[&strategy_seeds[..]];
+
+        // Caller-supplied accounts only reach the strategy when they are the ones registered with it
+        let adapter = StrategyAdapter {
+            strategy_program: strategy_program.to_account_info(),
+            strategy: strategy.to_account_info(),
+            vault: self.vault.to_account_info(),
+            vault_token_account: self.vault_token_account.to_account_info(),
+            strategy_token_account: strategy_token_account.to_account_info(),
+            deposit_token_mint: self.deposit_token_mint.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            adapter_accounts: strategy.adapter_accounts.clone(),
+            remaining_accounts: self.remaining_accounts,
+        };
+        adapter.withdraw(amount, strategy_signer_seeds)?;
+
+        self.vault_token_account.reload()?;
+        let amount_received = self.vault_token_account.amount
+            .checked_sub(idle)
+            .ok_or(VaultError::StrategyTransferMismatch)?;
+        require!(amount_received == amount, VaultError::StrategyTransferMismatch);
+
+        self.vault.deployed_assets = self.vault.deployed_assets.checked_sub(amount).ok_or(VaultError::MathOverflow)?;
+        strategy.deployed_assets = strategy.deployed_assets.checked_sub(amount).ok_or(VaultError::MathOverflow)?;
+
+        msg!("Deallocated {} tokens from strategy {} to pay a claim", amount, strategy.strategy_program);
+
+        Ok(())
+    }
 }
 
 
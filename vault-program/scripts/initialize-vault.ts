@@ -8,6 +8,7 @@ import {
 } from "@solana/web3.js";
 import { 
   TOKEN_PROGRAM_ID, 
+  TOKEN_2022_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
   getAssociatedTokenAddress,
 } from "@solana/spl-token";
@@ -34,6 +35,24 @@ const DEPOSIT_TOKEN_MINT = new PublicKey(
 // Durée d'une epoch en secondes (60 = 1 minute)
 const EPOCH_DURATION = 60;
 
+// Mode de mesure des epochs : { unixSeconds: {} }, { slots: {} } ou { solanaEpoch: {} }
+// (EPOCH_DURATION est exprimée dans l'unité du mode choisi)
+const EPOCH_MODE = { unixSeconds: {} };
+
+// Métadonnées du token IOU (affichées par les wallets)
+const IOU_NAME = "Kyros Vault Share";
+const IOU_SYMBOL = "kvUSDC";
+const IOU_URI = "";
+
+// Affiche les soldes IOU en tokens sous-jacents (extension interest-bearing Token-2022)
+const INTEREST_BEARING_IOU = false;
+
+// Restrictions sur les transferts de shares : { transferable: {} }, { nonTransferable: {} } ou { transferHook: {} }
+// (hors nonTransferable, appeler ensuite initialize_extra_account_meta_list du programme share-transfer-hook ;
+// en mode transferHook, ajouter aussi les wallets autorisés avec set_allowlist)
+const SHARE_TRANSFER_MODE = { transferable: {} };
+const SHARE_TRANSFER_HOOK_PROGRAM_ID = new PublicKey("97VVh6nbpScmjWxDKLriqayDTu2CmAztchYj6WBusXub");
+
 // ============================================
 
 async function main() {
@@ -119,14 +138,24 @@ export const CONFIG = {
   
   try {
     const tx = await program.methods
-      .initialize(new anchor.BN(EPOCH_DURATION))
+      .initialize(
+        new anchor.BN(EPOCH_DURATION),
+        EPOCH_MODE,
+        IOU_NAME,
+        IOU_SYMBOL,
+        IOU_URI,
+        INTEREST_BEARING_IOU,
+        SHARE_TRANSFER_MODE
+      )
       .accounts({
         authority: walletKeypair.publicKey,
         depositTokenMint: DEPOSIT_TOKEN_MINT,
         vault: vaultPDA,
         iouTokenMint: iouMintPDA,
         vaultTokenAccount: vaultTokenAccount,
+        transferHookProgram: "nonTransferable" in SHARE_TRANSFER_MODE ? null : SHARE_TRANSFER_HOOK_PROGRAM_ID,
         tokenProgram: TOKEN_PROGRAM_ID,
+        iouTokenProgram: TOKEN_2022_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: anchor.web3.SystemProgram.programId,
         rent: anchor.web3.SYSVAR_RENT_PUBKEY,